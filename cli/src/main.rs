@@ -0,0 +1,1019 @@
+mod output;
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anchor_lang::Discriminator;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use knostra_arcium::state::{
+    AssetStandard, BetAccount, CompressedProof, ComputationKind, CreateMarketParams, DealMode,
+    DeckAccount, DeckRules, GameAccount, MarketAccount, ResolutionMode, MAX_DECK_SIZE,
+};
+use knostra_client::instructions::DeckCard;
+use knostra_client::transaction::{self, JOIN_COMPUTE_UNITS, LOOKUP_TABLE_BATCH};
+use knostra_client::{accounts, instructions, offsets, pda, PROGRAM_ID};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+use crate::output::{print_bet, print_lookup_table, print_market, print_signature, OutputFormat};
+
+#[derive(Parser)]
+#[command(name = "knostra", about = "Operate knostra_arcium markets, decks and games")]
+struct Cli {
+    /// RPC endpoint of the cluster
+    #[arg(long, global = true, default_value = "http://localhost:8899")]
+    url: String,
+
+    /// Keypair file used as payer and signer
+    #[arg(long, global = true)]
+    keypair: Option<PathBuf>,
+
+    /// Arcium cluster account the MXE is assigned to (game commands only)
+    #[arg(long, global = true)]
+    cluster: Option<Pubkey>,
+
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Side {
+    Yes,
+    No,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    /// Resolved by the owner against a value
+    Value,
+    /// Settled by the result of the market's game
+    Game,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Deal {
+    /// Players pick their three cards
+    Chosen,
+    /// The MXE deals three cards from a shuffle of the deck
+    Shuffled,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Standard {
+    /// mpl-core asset
+    Core,
+    /// Token Metadata NFT, by mint
+    TokenMetadata,
+    /// Bubblegum compressed NFT, by asset id; needs a DAS-enabled RPC
+    Compressed,
+}
+
+impl From<Standard> for AssetStandard {
+    fn from(standard: Standard) -> Self {
+        match standard {
+            Standard::Core => AssetStandard::Core,
+            Standard::TokenMetadata => AssetStandard::TokenMetadata,
+            Standard::Compressed => AssetStandard::Compressed,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a market owned by the signer
+    Create {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value = "")]
+        description: String,
+        #[arg(long)]
+        token: String,
+        /// Unix timestamp the market opens at
+        #[arg(long)]
+        start: u64,
+        /// Unix timestamp the market closes at
+        #[arg(long)]
+        end: u64,
+        /// One of >=, <=, >, <, ==
+        #[arg(long)]
+        op: String,
+        #[arg(long)]
+        target: u64,
+        /// Required bet amount in lamports
+        #[arg(long)]
+        bet_amount: u64,
+        #[arg(long)]
+        max_players: u64,
+        #[arg(long, value_enum, default_value_t = Mode::Value)]
+        mode: Mode,
+        /// Fewest cards a deck joining the game may hold
+        #[arg(long, default_value_t = DeckRules::DEFAULT.min_size)]
+        deck_min: u8,
+        /// Most cards a deck joining the game may hold
+        #[arg(long, default_value_t = DeckRules::DEFAULT.max_size)]
+        deck_max: u8,
+        /// mpl-core collection every card must belong to
+        #[arg(long)]
+        collection: Option<Pubkey>,
+    },
+    /// Place a bet on a market
+    Bet {
+        #[arg(long)]
+        market: Pubkey,
+        /// Bet amount in lamports
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, value_enum)]
+        side: Side,
+    },
+    /// Resolve an ongoing market against a value
+    Resolve {
+        #[arg(long)]
+        market: Pubkey,
+        #[arg(long)]
+        value: u64,
+    },
+    /// Cancel a market that has not started
+    Cancel {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Claim winnings or a refund for the signer's bet
+    Claim {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Claim the creator fees of a resolved market
+    ClaimFees {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Check the treasury vault against the booked amounts
+    Reconcile {
+        #[arg(long)]
+        market: Pubkey,
+        /// Freeze the market if the vault holds less than is booked (owner only)
+        #[arg(long)]
+        freeze: bool,
+    },
+    /// Lift a reconcile freeze once the vault covers what is booked (owner only)
+    Unfreeze {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// End a game whose join or turn deadline has passed
+    ClaimForfeit {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Roll back a game computation whose callback never arrived
+    RollbackComputation {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Create a deck from NFTs owned by the signer. Cards go in the deck in
+    /// the order given, mpl-core assets first, then legacy, then compressed.
+    CreateDeck {
+        #[arg(long)]
+        seed: u64,
+        /// mpl-core assets
+        #[arg(long, value_delimiter = ',')]
+        mints: Vec<Pubkey>,
+        /// Token Metadata NFT mints
+        #[arg(long, value_delimiter = ',')]
+        legacy: Vec<Pubkey>,
+        /// Bubblegum asset ids; needs a DAS-enabled RPC
+        #[arg(long, value_delimiter = ',')]
+        compressed: Vec<Pubkey>,
+    },
+    /// Append NFTs owned by the signer to one of their decks, in the same
+    /// order as `create-deck`
+    AddDeckCards {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long, value_delimiter = ',')]
+        mints: Vec<Pubkey>,
+        #[arg(long, value_delimiter = ',')]
+        legacy: Vec<Pubkey>,
+        #[arg(long, value_delimiter = ',')]
+        compressed: Vec<Pubkey>,
+    },
+    /// Remove the card at an index of one of the signer's decks
+    RemoveDeckCard {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long)]
+        index: u16,
+    },
+    /// Replace the card at an index of one of the signer's decks
+    ReplaceDeckCard {
+        #[arg(long)]
+        seed: u64,
+        #[arg(long)]
+        index: u16,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, value_enum, default_value = "core")]
+        standard: Standard,
+    },
+    /// Close one of the signer's decks and reclaim its rent
+    CloseDeck {
+        #[arg(long)]
+        seed: u64,
+    },
+    /// Unlock a deck from the game it was seated in once that game allows it
+    ReleaseDeck {
+        #[arg(long)]
+        deck: Pubkey,
+    },
+    /// Queue the init_game computation of one of the signer's game-mode markets
+    InitGame {
+        #[arg(long)]
+        market: Pubkey,
+        #[arg(long)]
+        nonce: u128,
+        #[arg(long, value_enum, default_value = "chosen")]
+        deal: Deal,
+        /// Each player stakes a deck card the winner takes
+        #[arg(long)]
+        ante: bool,
+    },
+    /// Queue the join_game computation with pre-encrypted cards
+    JoinGame {
+        #[arg(long)]
+        market: Pubkey,
+        #[arg(long)]
+        deck: Pubkey,
+        /// Three hex-encoded 32 byte card ciphertexts
+        #[arg(long, value_delimiter = ',', num_args = 3, required = true)]
+        cards: Vec<String>,
+        /// Hex-encoded x25519 public key the cards were encrypted with
+        #[arg(long)]
+        pub_key: String,
+        #[arg(long)]
+        nonce: u128,
+        /// Deck indices of the cards to play, up to 20 from anywhere in the
+        /// deck; the first 20 cards when absent
+        #[arg(long, value_delimiter = ',')]
+        pool: Vec<u16>,
+        /// Index, in the pool, of the card staked in an ante game
+        #[arg(long)]
+        ante_card: Option<u8>,
+        /// Address lookup table to send the join through, extended with any
+        /// of its accounts it lacks; a new one is created when absent
+        #[arg(long)]
+        lookup_table: Option<Pubkey>,
+    },
+    /// Queue the deal_hand computation to join a shuffled game
+    DealHand {
+        #[arg(long)]
+        market: Pubkey,
+        #[arg(long)]
+        deck: Pubkey,
+        /// Hex-encoded x25519 public key the dealt hand is encrypted to
+        #[arg(long)]
+        pub_key: String,
+        #[arg(long)]
+        nonce: u128,
+        /// Deck indices of the cards to deal from, 20 from anywhere in the
+        /// deck or all of a smaller one; the first 20 cards when absent
+        #[arg(long, value_delimiter = ',')]
+        pool: Vec<u16>,
+        /// Index, in the pool, of the card staked in an ante game
+        #[arg(long)]
+        ante_card: Option<u8>,
+        /// Address lookup table to send the join through, extended with any
+        /// of its accounts it lacks; a new one is created when absent
+        #[arg(long)]
+        lookup_table: Option<Pubkey>,
+    },
+    /// Hand out the escrowed ante cards of a game once they are due
+    SettleAnte {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Queue the play_turn computation with a pre-encrypted card index
+    PlayTurn {
+        #[arg(long)]
+        market: Pubkey,
+        /// Hex-encoded 32 byte ciphertext of the card index
+        #[arg(long)]
+        choice: String,
+        /// Hex-encoded x25519 public key the choice was encrypted with
+        #[arg(long)]
+        pub_key: String,
+        #[arg(long)]
+        nonce: u128,
+    },
+    /// Queue the reveal of a finished game into its history account
+    RevealGame {
+        #[arg(long)]
+        market: Pubkey,
+    },
+    /// Print a decoded account
+    Show {
+        #[command(subcommand)]
+        target: ShowTarget,
+    },
+    /// List program accounts
+    List {
+        #[command(subcommand)]
+        target: ListTarget,
+    },
+    /// Run one command per line from a file, stopping at the first failure.
+    /// Global flags are taken from this invocation, not from the file.
+    Batch { file: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum ShowTarget {
+    Market { address: Pubkey },
+    Bet { address: Pubkey },
+    /// Revealed record of a market's finished game
+    History {
+        #[arg(long)]
+        market: Pubkey,
+    },
+}
+
+#[derive(Subcommand)]
+enum ListTarget {
+    Markets {
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    Bets {
+        #[arg(long)]
+        market: Option<Pubkey>,
+    },
+    /// Computations queued for a market's game
+    Computations {
+        #[arg(long)]
+        market: Pubkey,
+    },
+}
+
+struct App {
+    rpc: RpcClient,
+    payer: Keypair,
+    cluster: Option<Pubkey>,
+    output: OutputFormat,
+}
+
+impl App {
+    fn new(cli: &Cli) -> Result<Self> {
+        let keypair_path = match &cli.keypair {
+            Some(path) => path.clone(),
+            None => default_keypair_path()?,
+        };
+        let payer = read_keypair_file(&keypair_path)
+            .map_err(|e| anyhow!("reading keypair {}: {e}", keypair_path.display()))?;
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+            payer,
+            cluster: cli.cluster,
+            output: cli.output,
+        })
+    }
+
+    fn cluster(&self) -> Result<Pubkey> {
+        self.cluster
+            .ok_or_else(|| anyhow!("--cluster is required for game commands"))
+    }
+
+    fn send(&self, ix: Instruction) -> Result<()> {
+        let signature = self.submit(ix)?;
+        print_signature(self.output, &signature);
+        Ok(())
+    }
+
+    fn submit(&self, ix: Instruction) -> Result<Signature> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&tx)?)
+    }
+
+    /// Sends `ix` as a v0 transaction with a raised compute limit, its accounts
+    /// looked up through `lookup_table` (see `lookup_table`). Joins carry a
+    /// pool of cards, more accounts than a legacy transaction holds.
+    fn send_join(&self, ix: Instruction, lookup_table: Option<Pubkey>) -> Result<()> {
+        let payer = self.payer.pubkey();
+        let addresses = transaction::lookup_addresses(&payer, std::slice::from_ref(&ix));
+        let table = self.lookup_table(lookup_table, &addresses)?;
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let message = transaction::message(
+            &payer,
+            &[ix],
+            JOIN_COMPUTE_UNITS,
+            &[table],
+            blockhash,
+        )?;
+        let tx = VersionedTransaction::try_new(message, &[&self.payer])?;
+        let signature = self.rpc.send_and_confirm_transaction(&tx)?;
+        print_signature(self.output, &signature);
+        Ok(())
+    }
+
+    /// The lookup table `existing`, or a new one owned by the payer, extended
+    /// with whichever of `addresses` it lacks.
+    fn lookup_table(
+        &self,
+        existing: Option<Pubkey>,
+        addresses: &[Pubkey],
+    ) -> Result<AddressLookupTableAccount> {
+        let payer = self.payer.pubkey();
+        let (key, mut held) = match existing {
+            Some(key) => {
+                let data = self.rpc.get_account_data(&key)?;
+                let table = AddressLookupTable::deserialize(&data)
+                    .map_err(|e| anyhow!("decoding lookup table {key}: {e}"))?;
+                (key, table.addresses.to_vec())
+            }
+            None => {
+                let slot = self.rpc.get_slot_with_commitment(CommitmentConfig::finalized())?;
+                let (ix, key) = create_lookup_table(payer, payer, slot);
+                self.submit(ix)?;
+                print_lookup_table(self.output, &key);
+                (key, Vec::new())
+            }
+        };
+
+        let missing: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|address| !held.contains(address))
+            .copied()
+            .collect();
+        for batch in missing.chunks(LOOKUP_TABLE_BATCH) {
+            self.submit(extend_lookup_table(key, payer, Some(payer), batch.to_vec()))?;
+        }
+        if !missing.is_empty() {
+            // Addresses added to a table can only be looked up from the next slot
+            let extended = self.rpc.get_slot()?;
+            while self.rpc.get_slot()? <= extended {
+                thread::sleep(Duration::from_millis(200));
+            }
+            held.extend(missing);
+        }
+        Ok(AddressLookupTableAccount {
+            key,
+            addresses: held,
+        })
+    }
+
+    fn market(&self, address: &Pubkey) -> Result<MarketAccount> {
+        let data = self.rpc.get_account_data(address)?;
+        accounts::market_account(&data).map_err(|e| anyhow!("decoding market {address}: {e}"))
+    }
+
+    fn game(&self, market: &Pubkey) -> Result<GameAccount> {
+        let address = pda::game(market).0;
+        let data = self.rpc.get_account_data(&address)?;
+        accounts::game_account(&data).map_err(|e| anyhow!("decoding game {address}: {e}"))
+    }
+
+    /// `mint` as a deck card held by `owner`, with the accounts showing it.
+    fn deck_card(&self, owner: &Pubkey, mint: Pubkey, standard: AssetStandard) -> Result<DeckCard> {
+        Ok(match standard {
+            AssetStandard::Core => DeckCard::core(mint),
+            AssetStandard::TokenMetadata => {
+                let holder = self
+                    .rpc
+                    .get_token_largest_accounts(&mint)?
+                    .into_iter()
+                    .find(|account| account.amount.amount == "1")
+                    .ok_or_else(|| anyhow!("no token account holds {mint}"))?;
+                DeckCard::token_metadata(mint, holder.address.parse()?)
+            }
+            AssetStandard::Compressed => {
+                let asset = self.das("getAsset", &mint)?;
+                let proof = self.das("getAssetProof", &mint)?;
+                let nonce = asset["compression"]["leaf_id"]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("{mint} is not compressed"))?;
+                // Bubblegum leaves without a delegate hold the owner there
+                let delegate = match asset["ownership"]["delegate"].as_str() {
+                    Some(delegate) => delegate.parse()?,
+                    None => *owner,
+                };
+                let nodes = proof["proof"]
+                    .as_array()
+                    .ok_or_else(|| anyhow!("no proof for {mint}"))?
+                    .iter()
+                    .map(das_hash)
+                    .collect::<Result<Vec<_>>>()?;
+                DeckCard::compressed(
+                    mint,
+                    Pubkey::new_from_array(das_hash(&proof["tree_id"])?),
+                    CompressedProof {
+                        root: das_hash(&proof["root"])?,
+                        data_hash: das_hash(&asset["compression"]["data_hash"])?,
+                        creator_hash: das_hash(&asset["compression"]["creator_hash"])?,
+                        delegate,
+                        nonce,
+                        index: nonce as u32,
+                        proof_len: 0,
+                    },
+                    &nodes,
+                )
+            }
+        })
+    }
+
+    fn deck_cards(
+        &self,
+        owner: &Pubkey,
+        mints: Vec<Pubkey>,
+        legacy: Vec<Pubkey>,
+        compressed: Vec<Pubkey>,
+    ) -> Result<Vec<DeckCard>> {
+        let core = mints.into_iter().map(|mint| (mint, AssetStandard::Core));
+        let legacy = legacy
+            .into_iter()
+            .map(|mint| (mint, AssetStandard::TokenMetadata));
+        let compressed = compressed
+            .into_iter()
+            .map(|mint| (mint, AssetStandard::Compressed));
+        core.chain(legacy)
+            .chain(compressed)
+            .map(|(mint, standard)| self.deck_card(owner, mint, standard))
+            .collect()
+    }
+
+    /// The card accounts joining a game with, or releasing, the deck pool
+    /// `deck` takes.
+    fn pool_cards(&self, deck: &DeckAccount) -> Result<Vec<AccountMeta>> {
+        let mut cards = instructions::deck_cards(deck);
+        cards.extend(self.card_collections(deck)?);
+        Ok(cards)
+    }
+
+    /// The collection accounts that end the card accounts of `deck` when its
+    /// cards don't share a collection.
+    fn card_collections(&self, deck: &DeckAccount) -> Result<Vec<AccountMeta>> {
+        if deck.collection != Pubkey::default() {
+            return Ok(Vec::new());
+        }
+        let assets: Vec<Pubkey> = deck.core_cards().map(|(_, card)| *card).collect();
+        let mut collections = Vec::new();
+        for chunk in assets.chunks(100) {
+            for (asset, account) in chunk.iter().zip(self.rpc.get_multiple_accounts(chunk)?) {
+                let account = account.ok_or_else(|| anyhow!("asset {asset} not found"))?;
+                let collection = accounts::asset_collection(&account.data)
+                    .map_err(|e| anyhow!("decoding asset {asset}: {e}"))?;
+                collections.push(collection);
+            }
+        }
+        Ok(instructions::card_collections(deck, collections))
+    }
+
+    /// Calls a DAS method taking an asset id.
+    fn das(&self, method: &'static str, id: &Pubkey) -> Result<Value> {
+        self.rpc
+            .send(
+                RpcRequest::Custom { method },
+                json!({ "id": id.to_string() }),
+            )
+            .with_context(|| format!("{method} {id}, is the RPC DAS-enabled?"))
+    }
+
+    fn program_accounts(&self, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let accounts = self.rpc.get_program_accounts_with_config(
+            &PROGRAM_ID,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?;
+        Ok(accounts
+            .into_iter()
+            .map(|(address, account)| (address, account.data))
+            .collect())
+    }
+}
+
+/// DAS reports hashes and keys alike in base58.
+fn das_hash(value: &Value) -> Result<[u8; 32]> {
+    let hash: Pubkey = value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected a base58 hash, got {value}"))?
+        .parse()?;
+    Ok(hash.to_bytes())
+}
+
+fn default_keypair_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME is not set, pass --keypair")?;
+    Ok(PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+fn parse_bytes32(hex: &str) -> Result<[u8; 32]> {
+    let hex = hex.trim_start_matches("0x");
+    if hex.len() != 64 {
+        bail!("expected 32 bytes of hex, got {hex}");
+    }
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .with_context(|| format!("invalid hex {hex}"))?;
+    }
+    Ok(out)
+}
+
+/// `pool`, or the first `MAX_DECK_SIZE` cards of `deck` when it is empty.
+fn deck_pool(deck: &DeckAccount, pool: Vec<u16>) -> Vec<u16> {
+    if !pool.is_empty() {
+        return pool;
+    }
+    (0..deck.nfts.len().min(MAX_DECK_SIZE) as u16).collect()
+}
+
+fn discriminator_filter(discriminator: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, discriminator.to_vec()))
+}
+
+fn run(app: &App, command: Command) -> Result<()> {
+    let payer = app.payer.pubkey();
+    match command {
+        Command::Create {
+            seed,
+            name,
+            description,
+            token,
+            start,
+            end,
+            op,
+            target,
+            bet_amount,
+            max_players,
+            mode,
+            deck_min,
+            deck_max,
+            collection,
+        } => {
+            let params = CreateMarketParams {
+                name,
+                description,
+                token,
+                market_start: start,
+                market_end: end,
+                relational_value: op,
+                target_value: target,
+                required_bet_amount: bet_amount,
+                max_player_count: max_players,
+                resolution_mode: match mode {
+                    Mode::Value => ResolutionMode::Value,
+                    Mode::Game => ResolutionMode::Game,
+                },
+                deck_rules: DeckRules {
+                    min_size: deck_min,
+                    max_size: deck_max,
+                    collection: collection.unwrap_or_default(),
+                },
+            };
+            app.send(instructions::create(payer, seed, params))?;
+            let (market, _) = pda::market(&payer, seed);
+            print_market(app.output, &market, &app.market(&market)?);
+        }
+        Command::Bet {
+            market,
+            amount,
+            side,
+        } => {
+            let choice = matches!(side, Side::Yes);
+            app.send(instructions::bet(market, payer, amount, choice))?;
+        }
+        Command::Resolve { market, value } => {
+            let owner = app.market(&market)?.owner;
+            app.send(instructions::resolve(market, owner, value))?;
+        }
+        Command::Cancel { market } => {
+            let creator = app.market(&market)?.owner;
+            app.send(instructions::cancel(market, creator))?;
+        }
+        Command::Claim { market } => app.send(instructions::claim(market, payer))?,
+        Command::ClaimFees { market } => app.send(instructions::claim_fees(market, payer))?,
+        Command::Reconcile { market, freeze } => {
+            app.send(instructions::reconcile(payer, market, freeze))?
+        }
+        Command::Unfreeze { market } => app.send(instructions::unfreeze(payer, market))?,
+        Command::ClaimForfeit { market } => app.send(instructions::claim_forfeit(market))?,
+        Command::RollbackComputation { market } => {
+            app.send(instructions::rollback_computation(market))?
+        }
+        Command::CreateDeck {
+            seed,
+            mints,
+            legacy,
+            compressed,
+        } => {
+            let cards = app.deck_cards(&payer, mints, legacy, compressed)?;
+            app.send(instructions::create_deck_with(payer, seed, cards))?
+        }
+        Command::AddDeckCards {
+            seed,
+            mints,
+            legacy,
+            compressed,
+        } => {
+            let deck = pda::deck(&payer, seed).0;
+            let cards = app.deck_cards(&payer, mints, legacy, compressed)?;
+            app.send(instructions::add_deck_cards_with(payer, deck, cards))?
+        }
+        Command::RemoveDeckCard { seed, index } => {
+            let deck = pda::deck(&payer, seed).0;
+            app.send(instructions::remove_deck_card(payer, deck, index))?
+        }
+        Command::ReplaceDeckCard {
+            seed,
+            index,
+            mint,
+            standard,
+        } => {
+            let deck = pda::deck(&payer, seed).0;
+            let card = app.deck_card(&payer, mint, standard.into())?;
+            app.send(instructions::replace_deck_card_with(
+                payer, deck, index, card,
+            ))?
+        }
+        Command::CloseDeck { seed } => {
+            let deck = pda::deck(&payer, seed).0;
+            app.send(instructions::close_deck(payer, deck))?
+        }
+        Command::ReleaseDeck { deck } => {
+            let deck_account = accounts::deck_account(&app.rpc.get_account_data(&deck)?)
+                .map_err(|e| anyhow!("decoding deck {deck}: {e}"))?;
+            let pool = deck_account
+                .select(&deck_account.locked_pool)
+                .map_err(|e| anyhow!("deck {deck} pool: {e}"))?;
+            let cards = app.pool_cards(&pool)?;
+            app.send(instructions::release_deck(payer, deck, deck_account.locked_game, cards))?
+        }
+        Command::InitGame {
+            market,
+            nonce,
+            deal,
+            ante,
+        } => {
+            // A game already on chain is a retry of its failed init
+            let offset = match app.rpc.get_account_data(&pda::game(&market).0) {
+                Ok(_) => offsets::next(&market, &app.game(&market)?, ComputationKind::InitGame, 0),
+                Err(_) => offsets::init_game(&market),
+            };
+            app.send(instructions::init_game(
+                payer,
+                app.cluster()?,
+                offset,
+                market,
+                nonce,
+                match deal {
+                    Deal::Chosen => DealMode::Chosen,
+                    Deal::Shuffled => DealMode::Shuffled,
+                },
+                ante,
+            ))?;
+        }
+        Command::JoinGame {
+            market,
+            deck,
+            cards,
+            pub_key,
+            nonce,
+            pool,
+            ante_card,
+            lookup_table,
+        } => {
+            let bet_address = pda::bet(&market, &payer).0;
+            let bet = accounts::bet_account(&app.rpc.get_account_data(&bet_address)?)
+                .map_err(|e| anyhow!("decoding bet {bet_address}: {e}"))?;
+            let side = if bet.choice { 0 } else { 1 };
+            let game = app.game(&market)?;
+            let offset = offsets::next(&market, &game, ComputationKind::JoinGame, side);
+            let deck_account = accounts::deck_account(&app.rpc.get_account_data(&deck)?)
+                .map_err(|e| anyhow!("decoding deck {deck}: {e}"))?;
+            let pool = deck_pool(&deck_account, pool);
+            let deck_pool = deck_account
+                .select(&pool)
+                .map_err(|e| anyhow!("deck {deck} pool {pool:?}: {e}"))?;
+            let deck_cards = app.pool_cards(&deck_pool)?;
+            let ix = instructions::join_game(
+                payer,
+                app.cluster()?,
+                offset,
+                market,
+                deck,
+                deck_cards,
+                parse_bytes32(&cards[0])?,
+                parse_bytes32(&cards[1])?,
+                parse_bytes32(&cards[2])?,
+                parse_bytes32(&pub_key)?,
+                nonce,
+                ante_card,
+                pool,
+            );
+            app.send_join(ix, lookup_table)?;
+        }
+        Command::DealHand {
+            market,
+            deck,
+            pub_key,
+            nonce,
+            pool,
+            ante_card,
+            lookup_table,
+        } => {
+            let bet_address = pda::bet(&market, &payer).0;
+            let bet = accounts::bet_account(&app.rpc.get_account_data(&bet_address)?)
+                .map_err(|e| anyhow!("decoding bet {bet_address}: {e}"))?;
+            let side = if bet.choice { 0 } else { 1 };
+            let game = app.game(&market)?;
+            let offset = offsets::next(&market, &game, ComputationKind::JoinGame, side);
+            let deck_account = accounts::deck_account(&app.rpc.get_account_data(&deck)?)
+                .map_err(|e| anyhow!("decoding deck {deck}: {e}"))?;
+            let pool = deck_pool(&deck_account, pool);
+            let deck_pool = deck_account
+                .select(&pool)
+                .map_err(|e| anyhow!("deck {deck} pool {pool:?}: {e}"))?;
+            let deck_cards = app.pool_cards(&deck_pool)?;
+            let ix = instructions::deal_hand(
+                payer,
+                app.cluster()?,
+                offset,
+                market,
+                deck,
+                deck_cards,
+                parse_bytes32(&pub_key)?,
+                nonce,
+                ante_card,
+                pool,
+            );
+            app.send_join(ix, lookup_table)?;
+        }
+        Command::SettleAnte { market } => {
+            let game = app.game(&market)?;
+            let mut collections = [None; 2];
+            for (side, stake) in [game.yes_ante, game.no_ante].iter().enumerate() {
+                if game.ante_recipient(side as u8).is_some() {
+                    let data = app.rpc.get_account_data(&stake.asset)?;
+                    collections[side] = accounts::asset_collection(&data)
+                        .map_err(|e| anyhow!("decoding asset {}: {e}", stake.asset))?;
+                }
+            }
+            app.send(instructions::settle_ante(
+                payer,
+                market,
+                instructions::ante_accounts(&game, collections),
+            ))?
+        }
+        Command::PlayTurn {
+            market,
+            choice,
+            pub_key,
+            nonce,
+        } => {
+            let game = app.game(&market)?;
+            let side = if game.player_yes == payer { 0 } else { 1 };
+            let offset = offsets::next(&market, &game, ComputationKind::PlayTurn, side);
+            app.send(instructions::play_turn(
+                payer,
+                app.cluster()?,
+                offset,
+                market,
+                parse_bytes32(&choice)?,
+                parse_bytes32(&pub_key)?,
+                nonce,
+            ))?;
+        }
+        Command::RevealGame { market } => {
+            let game = app.game(&market)?;
+            let offset = offsets::next(&market, &game, ComputationKind::RevealGame, 0);
+            app.send(instructions::reveal_game(
+                payer,
+                app.cluster()?,
+                offset,
+                market,
+            ))?;
+        }
+        Command::Show { target } => match target {
+            ShowTarget::Market { address } => {
+                print_market(app.output, &address, &app.market(&address)?)
+            }
+            ShowTarget::Bet { address } => {
+                let data = app.rpc.get_account_data(&address)?;
+                let bet = accounts::bet_account(&data)
+                    .map_err(|e| anyhow!("decoding bet {address}: {e}"))?;
+                print_bet(app.output, &address, &bet);
+            }
+            ShowTarget::History { market } => {
+                let address = pda::history(&pda::game(&market).0).0;
+                let data = app.rpc.get_account_data(&address)?;
+                let history = accounts::game_history(&data)
+                    .map_err(|e| anyhow!("decoding history {address}: {e}"))?;
+                output::print_history(app.output, &address, &history);
+            }
+        },
+        Command::List { target } => match target {
+            ListTarget::Markets { owner } => {
+                let mut filters = vec![discriminator_filter(MarketAccount::DISCRIMINATOR)];
+                if let Some(owner) = owner {
+                    // discriminator (8) + bump (1)
+                    filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        9,
+                        owner.to_bytes().to_vec(),
+                    )));
+                }
+                let markets = app
+                    .program_accounts(filters)?
+                    .into_iter()
+                    .map(|(address, data)| Ok((address, accounts::market_account(&data)?)))
+                    .collect::<anchor_lang::Result<Vec<_>>>()
+                    .map_err(|e| anyhow!("decoding markets: {e}"))?;
+                output::print_markets(app.output, &markets);
+            }
+            ListTarget::Bets { market } => {
+                let mut filters = vec![discriminator_filter(BetAccount::DISCRIMINATOR)];
+                if let Some(market) = market {
+                    filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                        8,
+                        market.to_bytes().to_vec(),
+                    )));
+                }
+                let bets = app
+                    .program_accounts(filters)?
+                    .into_iter()
+                    .map(|(address, data)| Ok((address, accounts::bet_account(&data)?)))
+                    .collect::<anchor_lang::Result<Vec<_>>>()
+                    .map_err(|e| anyhow!("decoding bets: {e}"))?;
+                output::print_bets(app.output, &bets);
+            }
+            ListTarget::Computations { market } => {
+                let game = app.game(&market)?;
+                let mut queued = Vec::new();
+                let steps = offsets::history(&market, &game);
+                for chunk in steps.chunks(100) {
+                    let addresses: Vec<Pubkey> = chunk.iter().map(|step| step.computation).collect();
+                    let found = app.rpc.get_multiple_accounts(&addresses)?;
+                    queued.extend(
+                        chunk
+                            .iter()
+                            .zip(found)
+                            .filter(|(_, account)| account.is_some())
+                            .map(|(step, _)| step),
+                    );
+                }
+                output::print_computations(app.output, &queued);
+            }
+        },
+        Command::Batch { file } => {
+            let script = fs::read_to_string(&file)
+                .with_context(|| format!("reading batch file {}", file.display()))?;
+            for (number, line) in script.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let args = std::iter::once("knostra").chain(line.split_whitespace());
+                let cli = Cli::try_parse_from(args)
+                    .with_context(|| format!("{}:{}", file.display(), number + 1))?;
+                if matches!(cli.command, Command::Batch { .. }) {
+                    bail!("{}:{}: nested batch files are not supported", file.display(), number + 1);
+                }
+                run(app, cli.command)
+                    .with_context(|| format!("{}:{}: {line}", file.display(), number + 1))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let app = App::new(&cli)?;
+    run(&app, cli.command)
+}
@@ -0,0 +1,236 @@
+use clap::ValueEnum;
+use knostra_arcium::state::{
+    BetAccount, ComputationKind, GameHistory, MarketAccount, ResolutionMode, Status,
+};
+use knostra_client::offsets::GameStep;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::NotStarted => "not_started",
+        Status::Ongoing => "ongoing",
+        Status::ResolvedYes => "resolved_yes",
+        Status::ResolvedNo => "resolved_no",
+        Status::Completed => "completed",
+        Status::Cancelled => "cancelled",
+        Status::Frozen => "frozen",
+    }
+}
+
+fn mode_name(mode: ResolutionMode) -> &'static str {
+    match mode {
+        ResolutionMode::Value => "value",
+        ResolutionMode::Game => "game",
+    }
+}
+
+fn market_json(address: &Pubkey, market: &MarketAccount) -> Value {
+    json!({
+        "address": address.to_string(),
+        "owner": market.owner.to_string(),
+        "market_id": market.market_id,
+        "name": market.name,
+        "description": market.description,
+        "token": market.token,
+        "market_start": market.market_start,
+        "market_end": market.market_end,
+        "relational_value": market.relational_value,
+        "target_value": market.target_value,
+        "resolve_value": market.resolve_value,
+        "resolution_mode": mode_name(market.resolution_mode),
+        "deck_rules": {
+            "min_size": market.deck_rules.min_size,
+            "max_size": market.deck_rules.max_size,
+            "collection": (market.deck_rules.collection != Pubkey::default())
+                .then(|| market.deck_rules.collection.to_string()),
+        },
+        "status": status_name(market.status),
+        "required_bet_amount": market.required_bet_amount,
+        "max_player_count": market.max_player_count,
+        "created_at": market.created_at,
+        "updated_at": market.updated_at,
+    })
+}
+
+fn bet_json(address: &Pubkey, bet: &BetAccount) -> Value {
+    json!({
+        "address": address.to_string(),
+        "market": bet.market_account.to_string(),
+        "user": bet.user.to_string(),
+        "bet_amount": bet.bet_amount,
+        "choice": if bet.choice { "yes" } else { "no" },
+        "claimed": bet.claimed,
+    })
+}
+
+fn kind_name(kind: ComputationKind) -> &'static str {
+    match kind {
+        ComputationKind::InitGame => "init_game",
+        ComputationKind::JoinGame => "join_game",
+        ComputationKind::PlayTurn => "play_turn",
+        ComputationKind::RevealGame => "reveal_game",
+    }
+}
+
+fn step_json(step: &GameStep) -> Value {
+    json!({
+        "kind": kind_name(step.kind),
+        "turn": step.turn,
+        "side": if step.side == 0 { "yes" } else { "no" },
+        "attempt": step.attempt,
+        "offset": step.offset,
+        "computation": step.computation.to_string(),
+    })
+}
+
+fn history_json(address: &Pubkey, history: &GameHistory) -> Value {
+    json!({
+        "address": address.to_string(),
+        "game": history.game_account.to_string(),
+        "market": history.market_account.to_string(),
+        "player_yes": history.player_yes.to_string(),
+        "player_no": history.player_no.to_string(),
+        "yes_cards": history.yes_cards,
+        "no_cards": history.no_cards,
+        "yes_powers": history.yes_powers,
+        "yes_elements": history.yes_elements,
+        "no_powers": history.no_powers,
+        "no_elements": history.no_elements,
+        "yes_rounds": history.yes_rounds,
+        "no_rounds": history.no_rounds,
+        "round_winners": history.round_winners,
+        "result": history.result,
+        "revealed": history.revealed,
+        "revealed_at": history.revealed_at,
+    })
+}
+
+/// Prints `rows` as a fixed-width table, taking the columns from the first row.
+fn print_table(rows: &[Value]) {
+    let Some(Value::Object(first)) = rows.first() else {
+        println!("(none)");
+        return;
+    };
+    let columns: Vec<&String> = first.keys().collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| match &row[column.as_str()] {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(column.len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |values: Vec<&str>| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(columns.iter().map(|c| c.as_str()).collect());
+    for row in &cells {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
+
+/// Prints a single object as key/value pairs.
+fn print_record(value: &Value) {
+    if let Value::Object(map) = value {
+        let width = map.keys().map(String::len).max().unwrap_or(0);
+        for (key, value) in map {
+            match value {
+                Value::String(s) => println!("{key:<width$}  {s}"),
+                other => println!("{key:<width$}  {other}"),
+            }
+        }
+    }
+}
+
+fn print_one(format: OutputFormat, value: Value) {
+    match format {
+        OutputFormat::Table => print_record(&value),
+        OutputFormat::Json => println!("{value}"),
+    }
+}
+
+fn print_many(format: OutputFormat, values: Vec<Value>) {
+    match format {
+        OutputFormat::Table => print_table(&values),
+        OutputFormat::Json => println!("{}", Value::Array(values)),
+    }
+}
+
+pub fn print_signature(format: OutputFormat, signature: &Signature) {
+    match format {
+        OutputFormat::Table => println!("signature  {signature}"),
+        OutputFormat::Json => println!("{}", json!({ "signature": signature.to_string() })),
+    }
+}
+
+pub fn print_lookup_table(format: OutputFormat, address: &Pubkey) {
+    match format {
+        OutputFormat::Table => println!("lookup_table  {address}"),
+        OutputFormat::Json => println!("{}", json!({ "lookup_table": address.to_string() })),
+    }
+}
+
+pub fn print_market(format: OutputFormat, address: &Pubkey, market: &MarketAccount) {
+    print_one(format, market_json(address, market));
+}
+
+pub fn print_bet(format: OutputFormat, address: &Pubkey, bet: &BetAccount) {
+    print_one(format, bet_json(address, bet));
+}
+
+pub fn print_markets(format: OutputFormat, markets: &[(Pubkey, MarketAccount)]) {
+    print_many(
+        format,
+        markets
+            .iter()
+            .map(|(address, market)| market_json(address, market))
+            .collect(),
+    );
+}
+
+pub fn print_bets(format: OutputFormat, bets: &[(Pubkey, BetAccount)]) {
+    print_many(
+        format,
+        bets.iter()
+            .map(|(address, bet)| bet_json(address, bet))
+            .collect(),
+    );
+}
+
+pub fn print_computations(format: OutputFormat, steps: &[&GameStep]) {
+    print_many(format, steps.iter().map(|step| step_json(step)).collect());
+}
+
+pub fn print_history(format: OutputFormat, address: &Pubkey, history: &GameHistory) {
+    print_one(format, history_json(address, history));
+}
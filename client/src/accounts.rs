@@ -0,0 +1,46 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Result};
+use knostra_arcium::state::{
+    BetAccount, DeckAccount, GameAccount, GameHistory, MarketAccount, TreasuryAccount,
+};
+use mpl_core::accounts::BaseAssetV1;
+use mpl_core::types::UpdateAuthority;
+
+/// Decodes raw account data, checking the Anchor discriminator.
+pub fn deserialize<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    let mut data = data;
+    T::try_deserialize(&mut data)
+}
+
+pub fn market_account(data: &[u8]) -> Result<MarketAccount> {
+    deserialize(data)
+}
+
+pub fn treasury_account(data: &[u8]) -> Result<TreasuryAccount> {
+    deserialize(data)
+}
+
+pub fn bet_account(data: &[u8]) -> Result<BetAccount> {
+    deserialize(data)
+}
+
+pub fn deck_account(data: &[u8]) -> Result<DeckAccount> {
+    deserialize(data)
+}
+
+pub fn game_account(data: &[u8]) -> Result<GameAccount> {
+    deserialize(data)
+}
+
+pub fn game_history(data: &[u8]) -> Result<GameHistory> {
+    deserialize(data)
+}
+
+/// The collection an mpl-core asset belongs to, if any.
+pub fn asset_collection(data: &[u8]) -> std::io::Result<Option<Pubkey>> {
+    let asset = BaseAssetV1::from_bytes(data)?;
+    Ok(match asset.update_authority {
+        UpdateAuthority::Collection(collection) => Some(collection),
+        _ => None,
+    })
+}
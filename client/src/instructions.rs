@@ -0,0 +1,784 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use arcium_anchor::prelude::{
+    comp_def_offset, ARCIUM_CLOCK_ACCOUNT_ADDRESS, ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    ARCIUM_PROG_ID,
+};
+use knostra_arcium::{
+    accounts, instruction, AssetStandard, CompressedProof, CreateMarketParams, DealMode,
+    DeckAccount, GameAccount, ACCOUNT_COMPRESSION_ID,
+};
+use mpl_token_metadata::accounts::Metadata;
+
+use crate::{offsets, pda, PROGRAM_ID};
+
+fn build(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining_accounts);
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: metas,
+        data: data.data(),
+    }
+}
+
+pub fn init_add_together_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitAddTogetherCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("add_together")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitAddTogetherCompDef {},
+        vec![],
+    )
+}
+
+pub fn init_init_game_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitInitGameCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("init_game")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitInitGameCompDef {},
+        vec![],
+    )
+}
+
+pub fn init_join_game_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitJoinGameCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("join_game")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitJoinGameCompDef {},
+        vec![],
+    )
+}
+
+pub fn init_deal_hand_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitDealHandCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("deal_hand")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitDealHandCompDef {},
+        vec![],
+    )
+}
+
+pub fn init_play_turn_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitPlayTurnCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("play_turn")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitPlayTurnCompDef {},
+        vec![],
+    )
+}
+
+pub fn init_reveal_game_comp_def(payer: Pubkey) -> Instruction {
+    build(
+        accounts::InitRevealGameCompDef {
+            payer,
+            mxe_account: pda::mxe(),
+            comp_def_account: pda::comp_def(comp_def_offset("reveal_game")),
+            arcium_program: ARCIUM_PROG_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitRevealGameCompDef {},
+        vec![],
+    )
+}
+
+/// `cluster` is the cluster account the MXE is assigned to. The computation
+/// offset is derived from `payer` and `nonce` (see `offsets::add_together`).
+pub fn add_together(
+    payer: Pubkey,
+    cluster: Pubkey,
+    ciphertext_0: [u8; 32],
+    ciphertext_1: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Instruction {
+    let computation_offset = offsets::add_together(&payer, nonce);
+    build(
+        accounts::AddTogether {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("add_together")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+        },
+        instruction::AddTogether {
+            computation_offset,
+            ciphertext_0,
+            ciphertext_1,
+            pub_key,
+            nonce,
+        },
+        vec![],
+    )
+}
+
+/// `payer` must own `market`, a game-mode market still open for bets.
+pub fn init_game(
+    payer: Pubkey,
+    cluster: Pubkey,
+    computation_offset: u64,
+    market: Pubkey,
+    nonce: u128,
+    deal_mode: DealMode,
+    ante: bool,
+) -> Instruction {
+    build(
+        accounts::InitGame {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("init_game")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+            market_account: market,
+            game_account: pda::game(&market).0,
+        },
+        instruction::InitGame {
+            computation_offset,
+            nonce,
+            deal_mode,
+            ante,
+        },
+        vec![],
+    )
+}
+
+/// The card indices are encrypted under the shared secret of `pub_key` and the
+/// MXE key; the player's views of the game are encrypted to the same key.
+/// The player plays the cards of `deck` at the indices in `deck_pool` (see
+/// `DeckAccount::select`): `cards` are `deck_cards` of that pool followed by
+/// its `card_collections`. The pool is frozen while it plays, and card indices
+/// are indices into it. A full pool needs an address lookup table and a raised
+/// compute limit. Ante games take `ante_card`, the index of the card put in
+/// escrow.
+#[allow(clippy::too_many_arguments)]
+pub fn join_game(
+    payer: Pubkey,
+    cluster: Pubkey,
+    computation_offset: u64,
+    market: Pubkey,
+    deck: Pubkey,
+    cards: Vec<AccountMeta>,
+    player_cards1: [u8; 32],
+    player_cards2: [u8; 32],
+    player_cards3: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+    ante_card: Option<u8>,
+    deck_pool: Vec<u16>,
+) -> Instruction {
+    build(
+        accounts::JoinGame {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("join_game")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+            game_account: pda::game(&market).0,
+            deck_account: deck,
+            bet_account: pda::bet(&market, &payer).0,
+            market_account: market,
+            freeze_authority: pda::freeze_authority().0,
+            mpl_core_program: mpl_core::ID,
+        },
+        instruction::JoinGame {
+            computation_offset,
+            player_cards1,
+            player_cards2,
+            player_cards3,
+            pub_key,
+            nonce,
+            ante_card,
+            deck_pool,
+        },
+        cards,
+    )
+}
+
+/// Joins a shuffled game; the MXE deals three cards from `deck` and encrypts the
+/// player's view of the hand to `pub_key`. `cards` and `deck_pool` are as for
+/// `join_game`.
+#[allow(clippy::too_many_arguments)]
+pub fn deal_hand(
+    payer: Pubkey,
+    cluster: Pubkey,
+    computation_offset: u64,
+    market: Pubkey,
+    deck: Pubkey,
+    cards: Vec<AccountMeta>,
+    pub_key: [u8; 32],
+    nonce: u128,
+    ante_card: Option<u8>,
+    deck_pool: Vec<u16>,
+) -> Instruction {
+    build(
+        accounts::DealHand {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("deal_hand")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+            game_account: pda::game(&market).0,
+            deck_account: deck,
+            bet_account: pda::bet(&market, &payer).0,
+            market_account: market,
+            freeze_authority: pda::freeze_authority().0,
+            mpl_core_program: mpl_core::ID,
+        },
+        instruction::DealHand {
+            computation_offset,
+            pub_key,
+            nonce,
+            ante_card,
+            deck_pool,
+        },
+        cards,
+    )
+}
+
+/// `card_choice` is the card index (0, 1 or 2) encrypted under the shared
+/// secret of `pub_key` and the MXE key.
+pub fn play_turn(
+    payer: Pubkey,
+    cluster: Pubkey,
+    computation_offset: u64,
+    market: Pubkey,
+    card_choice: [u8; 32],
+    pub_key: [u8; 32],
+    nonce: u128,
+) -> Instruction {
+    build(
+        accounts::PlayTurn {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("play_turn")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+            game_account: pda::game(&market).0,
+            market_account: market,
+            treasury_account: pda::treasury(&market).0,
+        },
+        instruction::PlayTurn {
+            computation_offset,
+            card_choice,
+            pub_key,
+            nonce,
+        },
+        vec![],
+    )
+}
+
+pub fn reveal_game(
+    payer: Pubkey,
+    cluster: Pubkey,
+    computation_offset: u64,
+    market: Pubkey,
+) -> Instruction {
+    let game_account = pda::game(&market).0;
+    build(
+        accounts::RevealGame {
+            payer,
+            sign_pda_account: pda::sign_pda().0,
+            mxe_account: pda::mxe(),
+            mempool_account: pda::mempool(),
+            executing_pool: pda::execpool(),
+            computation_account: pda::computation(computation_offset),
+            comp_def_account: pda::comp_def(comp_def_offset("reveal_game")),
+            cluster_account: cluster,
+            pool_account: ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+            clock_account: ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+            system_program: system_program::ID,
+            arcium_program: ARCIUM_PROG_ID,
+            game_account,
+            game_history: pda::history(&game_account).0,
+        },
+        instruction::RevealGame { computation_offset },
+        vec![],
+    )
+}
+
+pub fn create(user: Pubkey, seed: u64, params: CreateMarketParams) -> Instruction {
+    let (market_account, bump) = pda::market(&user, seed);
+    let (treasury_account, treasury_bump) = pda::treasury(&market_account);
+    build(
+        accounts::CreateMarket {
+            market_account,
+            treasury_account,
+            treasury_vault: pda::treasury_vault(&market_account).0,
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::Create {
+            seed,
+            params,
+            bump,
+            treasury_bump,
+        },
+        vec![],
+    )
+}
+
+pub fn bet(market: Pubkey, user: Pubkey, amount: u64, choice: bool) -> Instruction {
+    let (bet_account, bump) = pda::bet(&market, &user);
+    build(
+        accounts::PlaceBet {
+            treasury_account: pda::treasury(&market).0,
+            bet_account,
+            market_account: market,
+            treasury_vault: pda::treasury_vault(&market).0,
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::Bet {
+            amount,
+            choice,
+            bump,
+        },
+        vec![],
+    )
+}
+
+pub fn resolve(market: Pubkey, owner: Pubkey, resolve_value: u64) -> Instruction {
+    build(
+        accounts::ResolveMarket {
+            market_account: market,
+            treasury_account: pda::treasury(&market).0,
+            resolver_authority: pda::resolver_authority().0,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::Resolve { resolve_value },
+        vec![],
+    )
+}
+
+pub fn claim(market: Pubkey, user: Pubkey) -> Instruction {
+    build(
+        accounts::Claim {
+            treasury_account: pda::treasury(&market).0,
+            bet_account: pda::bet(&market, &user).0,
+            market_account: market,
+            treasury_vault: pda::treasury_vault(&market).0,
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::Claim {},
+        vec![],
+    )
+}
+
+pub fn cancel(market: Pubkey, creator: Pubkey) -> Instruction {
+    build(
+        accounts::CancelMarket {
+            treasury_account: pda::treasury(&market).0,
+            market_account: market,
+            creator,
+            system_program: system_program::ID,
+        },
+        instruction::Cancel {},
+        vec![],
+    )
+}
+
+pub fn claim_fees(market: Pubkey, user: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimFees {
+            treasury_account: pda::treasury(&market).0,
+            market_account: market,
+            treasury_vault: pda::treasury_vault(&market).0,
+            user,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimFees {},
+        vec![],
+    )
+}
+
+/// Anyone can reconcile; `freeze` takes the market owner as `authority`.
+pub fn reconcile(authority: Pubkey, market: Pubkey, freeze: bool) -> Instruction {
+    build(
+        accounts::ReconcileTreasury {
+            market_account: market,
+            treasury_account: pda::treasury(&market).0,
+            treasury_vault: pda::treasury_vault(&market).0,
+            authority,
+        },
+        instruction::Reconcile { freeze },
+        vec![],
+    )
+}
+
+pub fn unfreeze(owner: Pubkey, market: Pubkey) -> Instruction {
+    build(
+        accounts::UnfreezeTreasury {
+            market_account: market,
+            treasury_account: pda::treasury(&market).0,
+            treasury_vault: pda::treasury_vault(&market).0,
+            owner,
+        },
+        instruction::Unfreeze {},
+        vec![],
+    )
+}
+
+pub fn claim_forfeit(market: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimForfeit {
+            game_account: pda::game(&market).0,
+            market_account: market,
+            treasury_account: pda::treasury(&market).0,
+        },
+        instruction::ClaimForfeit {},
+        vec![],
+    )
+}
+
+pub fn rollback_computation(market: Pubkey) -> Instruction {
+    build(
+        accounts::RollbackComputation {
+            game_account: pda::game(&market).0,
+        },
+        instruction::RollbackComputation {},
+        vec![],
+    )
+}
+
+/// A card to put in a deck, with the accounts that show its owner holds it.
+pub struct DeckCard {
+    pub mint: Pubkey,
+    pub standard: AssetStandard,
+    pub accounts: Vec<AccountMeta>,
+    pub proof: Option<CompressedProof>,
+}
+
+impl DeckCard {
+    /// An mpl-core asset.
+    pub fn core(asset: Pubkey) -> Self {
+        DeckCard {
+            mint: asset,
+            standard: AssetStandard::Core,
+            accounts: vec![AccountMeta::new_readonly(asset, false)],
+            proof: None,
+        }
+    }
+
+    /// A Token Metadata NFT held in `token_account`.
+    pub fn token_metadata(mint: Pubkey, token_account: Pubkey) -> Self {
+        DeckCard {
+            mint,
+            standard: AssetStandard::TokenMetadata,
+            accounts: vec![
+                AccountMeta::new_readonly(token_account, false),
+                AccountMeta::new_readonly(Metadata::find_pda(&mint).0, false),
+            ],
+            proof: None,
+        }
+    }
+
+    /// A compressed NFT, leaf of `tree`; `nodes` are its merkle proof from the
+    /// leaf up, and set `proof.proof_len`.
+    pub fn compressed(
+        asset_id: Pubkey,
+        tree: Pubkey,
+        mut proof: CompressedProof,
+        nodes: &[[u8; 32]],
+    ) -> Self {
+        proof.proof_len = nodes.len() as u8;
+        let mut accounts = vec![
+            AccountMeta::new_readonly(ACCOUNT_COMPRESSION_ID, false),
+            AccountMeta::new_readonly(tree, false),
+        ];
+        accounts.extend(
+            nodes
+                .iter()
+                .map(|node| AccountMeta::new_readonly(Pubkey::new_from_array(*node), false)),
+        );
+        DeckCard {
+            mint: asset_id,
+            standard: AssetStandard::Compressed,
+            accounts,
+            proof: Some(proof),
+        }
+    }
+}
+
+struct CardArgs {
+    mints: Vec<Pubkey>,
+    standards: Vec<AssetStandard>,
+    proofs: Vec<CompressedProof>,
+    accounts: Vec<AccountMeta>,
+}
+
+fn card_args(cards: Vec<DeckCard>) -> CardArgs {
+    let mut args = CardArgs {
+        mints: Vec::new(),
+        standards: Vec::new(),
+        proofs: Vec::new(),
+        accounts: Vec::new(),
+    };
+    for card in cards {
+        args.mints.push(card.mint);
+        args.standards.push(card.standard);
+        args.proofs.extend(card.proof);
+        args.accounts.extend(card.accounts);
+    }
+    args
+}
+
+/// `mints` are mpl-core asset accounts; see `create_deck_with` for other
+/// standards.
+pub fn create_deck(owner: Pubkey, seed: u64, mints: Vec<Pubkey>) -> Instruction {
+    create_deck_with(owner, seed, mints.into_iter().map(DeckCard::core).collect())
+}
+
+pub fn create_deck_with(owner: Pubkey, seed: u64, cards: Vec<DeckCard>) -> Instruction {
+    let (deck_account, bump) = pda::deck(&owner, seed);
+    let CardArgs {
+        mints,
+        standards,
+        proofs,
+        accounts,
+    } = card_args(cards);
+    build(
+        accounts::CreateDeckAccount {
+            deck_account,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::CreateDeck {
+            seed,
+            mints,
+            standards,
+            proofs,
+            bump,
+        },
+        accounts,
+    )
+}
+
+/// `mints` are mpl-core asset accounts; see `add_deck_cards_with` for other
+/// standards.
+pub fn add_deck_cards(owner: Pubkey, deck: Pubkey, mints: Vec<Pubkey>) -> Instruction {
+    add_deck_cards_with(owner, deck, mints.into_iter().map(DeckCard::core).collect())
+}
+
+pub fn add_deck_cards_with(owner: Pubkey, deck: Pubkey, cards: Vec<DeckCard>) -> Instruction {
+    let CardArgs {
+        mints,
+        standards,
+        proofs,
+        accounts,
+    } = card_args(cards);
+    build(
+        accounts::AddDeckCards {
+            deck_account: deck,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::AddDeckCards {
+            mints,
+            standards,
+            proofs,
+        },
+        accounts,
+    )
+}
+
+pub fn remove_deck_card(owner: Pubkey, deck: Pubkey, index: u16) -> Instruction {
+    build(
+        accounts::RemoveDeckCard {
+            deck_account: deck,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::RemoveDeckCard { index },
+        vec![],
+    )
+}
+
+/// `mint` is an mpl-core asset account; see `replace_deck_card_with` for
+/// other standards.
+pub fn replace_deck_card(owner: Pubkey, deck: Pubkey, index: u16, mint: Pubkey) -> Instruction {
+    replace_deck_card_with(owner, deck, index, DeckCard::core(mint))
+}
+
+pub fn replace_deck_card_with(
+    owner: Pubkey,
+    deck: Pubkey,
+    index: u16,
+    card: DeckCard,
+) -> Instruction {
+    build(
+        accounts::UpdateDeck {
+            deck_account: deck,
+            owner,
+        },
+        instruction::ReplaceDeckCard {
+            index,
+            mint: card.mint,
+            standard: card.standard,
+            proof: card.proof,
+        },
+        card.accounts,
+    )
+}
+
+pub fn close_deck(owner: Pubkey, deck: Pubkey) -> Instruction {
+    build(
+        accounts::CloseDeck {
+            deck_account: deck,
+            owner,
+        },
+        instruction::CloseDeck {},
+        vec![],
+    )
+}
+
+/// The mpl-core accounts of a deck's cards, as `release_deck` takes them for
+/// the locked pool: the assets in deck order, then the collection they share,
+/// if any.
+pub fn deck_cards(deck: &DeckAccount) -> Vec<AccountMeta> {
+    let mut cards: Vec<AccountMeta> = deck
+        .core_cards()
+        .map(|(_, card)| AccountMeta::new(*card, false))
+        .collect();
+    if deck.collection != Pubkey::default() {
+        cards.push(AccountMeta::new(deck.collection, false));
+    }
+    cards
+}
+
+/// The accounts that end a deck's card accounts when its cards don't share a
+/// collection: each of `collections`, those of its mpl-core cards, once.
+pub fn card_collections(
+    deck: &DeckAccount,
+    collections: impl IntoIterator<Item = Option<Pubkey>>,
+) -> Vec<AccountMeta> {
+    let mut metas: Vec<AccountMeta> = Vec::new();
+    if deck.collection != Pubkey::default() {
+        return metas;
+    }
+    for collection in collections.into_iter().flatten() {
+        if !metas.iter().any(|meta| meta.pubkey == collection) {
+            metas.push(AccountMeta::new(collection, false));
+        }
+    }
+    metas
+}
+
+/// The accounts `settle_ante` takes for `game`. `collections` holds the
+/// collection of each side's escrowed asset, yes first.
+pub fn ante_accounts(game: &GameAccount, collections: [Option<Pubkey>; 2]) -> Vec<AccountMeta> {
+    let mut antes = Vec::new();
+    for side in [0u8, 1] {
+        let Some(recipient) = game.ante_recipient(side) else {
+            continue;
+        };
+        let stake = if side == 0 { game.yes_ante } else { game.no_ante };
+        antes.push(AccountMeta::new(stake.asset, false));
+        if let Some(collection) = collections[side as usize] {
+            antes.push(AccountMeta::new(collection, false));
+        }
+        antes.push(AccountMeta::new(recipient, false));
+    }
+    antes
+}
+
+/// `antes` come from `ante_accounts`.
+pub fn settle_ante(payer: Pubkey, market: Pubkey, antes: Vec<AccountMeta>) -> Instruction {
+    build(
+        accounts::SettleAnte {
+            game_account: pda::game(&market).0,
+            payer,
+            mpl_core_program: mpl_core::ID,
+            system_program: system_program::ID,
+        },
+        instruction::SettleAnte {},
+        antes,
+    )
+}
+
+/// Thaws and unlocks `deck` from `game`, the game it is locked in. `cards`
+/// come from `deck_cards` of the deck's `locked_pool`.
+pub fn release_deck(
+    payer: Pubkey,
+    deck: Pubkey,
+    game: Pubkey,
+    cards: Vec<AccountMeta>,
+) -> Instruction {
+    build(
+        accounts::ReleaseDeck {
+            deck_account: deck,
+            game_account: game,
+            payer,
+            freeze_authority: pda::freeze_authority().0,
+            mpl_core_program: mpl_core::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ReleaseDeck {},
+        cards,
+    )
+}
@@ -0,0 +1,11 @@
+//! Client helpers for the `knostra_arcium` program: PDA derivation,
+//! instruction builders, account deserializers, game computation offsets and
+//! versioned transactions.
+
+pub mod accounts;
+pub mod instructions;
+pub mod offsets;
+pub mod pda;
+pub mod transaction;
+
+pub use knostra_arcium::ID as PROGRAM_ID;
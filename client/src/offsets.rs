@@ -0,0 +1,69 @@
+//! Computation offsets of game steps and `add_together`, derived the same way
+//! the program checks them, so the computation behind any step of a game can
+//! be looked up.
+
+use anchor_lang::prelude::Pubkey;
+use knostra_arcium::state::{ComputationKind, GameAccount, ROUND_PENDING};
+
+use crate::pda;
+
+/// A game step that may have been queued, with the offset it uses and the
+/// Arcium computation account at that offset.
+pub struct GameStep {
+    pub kind: ComputationKind,
+    pub turn: u8,
+    pub side: u8,
+    pub attempt: u32,
+    pub offset: u64,
+    pub computation: Pubkey,
+}
+
+/// Offset of the `add_together` computation `payer` queues with `nonce`.
+pub fn add_together(payer: &Pubkey, nonce: u128) -> u64 {
+    GameAccount::add_together_offset(payer, nonce)
+}
+
+/// Offset of the `init_game` computation of a new game.
+pub fn init_game(market: &Pubkey) -> u64 {
+    GameAccount::computation_offset(&pda::game(market).0, ComputationKind::InitGame, 0, 0, 0)
+}
+
+/// Offset the program expects for `side`'s next `kind` computation on the game.
+pub fn next(market: &Pubkey, game: &GameAccount, kind: ComputationKind, side: u8) -> u64 {
+    game.next_offset(&pda::game(market).0, kind, side)
+}
+
+/// Every step the game could have queued so far, for each failure count it went
+/// through. Steps that never ran have no computation account, so filtering on
+/// that gives the game's computation history.
+pub fn history(market: &Pubkey, game: &GameAccount) -> Vec<GameStep> {
+    let game_key = pda::game(market).0;
+    let last_turn = game.current_turn.min(GameAccount::ROUNDS - 1);
+    let mut steps = Vec::new();
+    for attempt in 0..=game.failed_computations {
+        let mut push = |kind: ComputationKind, turn: u8, side: u8| {
+            let offset = GameAccount::computation_offset(&game_key, kind, turn, side, attempt);
+            steps.push(GameStep {
+                kind,
+                turn,
+                side,
+                attempt,
+                offset,
+                computation: pda::computation(offset),
+            });
+        };
+        push(ComputationKind::InitGame, 0, 0);
+        for side in 0..2 {
+            push(ComputationKind::JoinGame, 0, side);
+        }
+        for turn in 0..=last_turn {
+            for side in 0..2 {
+                push(ComputationKind::PlayTurn, turn, side);
+            }
+        }
+        if game.result != ROUND_PENDING {
+            push(ComputationKind::RevealGame, game.current_turn, 0);
+        }
+    }
+    steps
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::Pubkey;
+use arcium_anchor::prelude::*;
+
+use crate::PROGRAM_ID;
+
+pub fn market(owner: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"market", owner.as_ref(), &seed.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+pub fn treasury(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury", market.as_ref()], &PROGRAM_ID)
+}
+
+pub fn treasury_vault(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury_vault", market.as_ref()], &PROGRAM_ID)
+}
+
+pub fn bet(market: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bet", market.as_ref(), user.as_ref()], &PROGRAM_ID)
+}
+
+pub fn deck(owner: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"deck", owner.as_ref(), &seed.to_le_bytes()],
+        &PROGRAM_ID,
+    )
+}
+
+pub fn game(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game", market.as_ref()], &PROGRAM_ID)
+}
+
+pub fn history(game: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"history", game.as_ref()], &PROGRAM_ID)
+}
+
+pub fn freeze_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"freeze_authority"], &PROGRAM_ID)
+}
+
+pub fn resolver_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"resolver_authority"], &PROGRAM_ID)
+}
+
+// Arcium accounts, mirroring the `derive_*_pda!` macros used by the program.
+
+pub fn sign_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SIGN_PDA_SEED], &PROGRAM_ID)
+}
+
+pub fn mxe() -> Pubkey {
+    Pubkey::find_program_address(&[MXE_PDA_SEED, PROGRAM_ID.as_ref()], &ARCIUM_PROG_ID).0
+}
+
+pub fn mempool() -> Pubkey {
+    Pubkey::find_program_address(&[MEMPOOL_PDA_SEED, PROGRAM_ID.as_ref()], &ARCIUM_PROG_ID).0
+}
+
+pub fn execpool() -> Pubkey {
+    Pubkey::find_program_address(&[EXECPOOL_PDA_SEED, PROGRAM_ID.as_ref()], &ARCIUM_PROG_ID).0
+}
+
+pub fn computation(computation_offset: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COMP_PDA_SEED,
+            PROGRAM_ID.as_ref(),
+            &computation_offset.to_le_bytes(),
+        ],
+        &ARCIUM_PROG_ID,
+    )
+    .0
+}
+
+pub fn comp_def(comp_def_offset: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COMP_DEF_PDA_SEED,
+            PROGRAM_ID.as_ref(),
+            &comp_def_offset.to_le_bytes(),
+        ],
+        &ARCIUM_PROG_ID,
+    )
+    .0
+}
+
+pub fn cluster(cluster_offset: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[CLUSTER_PDA_SEED, &cluster_offset.to_le_bytes()],
+        &ARCIUM_PROG_ID,
+    )
+    .0
+}
@@ -0,0 +1,45 @@
+//! Versioned transactions for instructions that outgrow a legacy one, such as a
+//! join with a full pool of cards. Their accounts go in an address lookup table
+//! and a compute budget instruction raises the unit limit.
+
+use anchor_lang::prelude::Pubkey;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, CompileError, VersionedMessage};
+
+/// Compute units a join or deal may use: it freezes every mpl-core card of the
+/// pool by CPI before queueing its computation.
+pub const JOIN_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Addresses an `extend_lookup_table` instruction adds at a time, so that it
+/// fits a legacy transaction.
+pub const LOOKUP_TABLE_BATCH: usize = 20;
+
+/// The accounts of `instructions` a lookup table can hold for `payer`. Signers
+/// and the programs the instructions call must stay in the message itself.
+pub fn lookup_addresses(payer: &Pubkey, instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut addresses = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| ix.accounts.iter()) {
+        if !meta.is_signer && meta.pubkey != *payer && !addresses.contains(&meta.pubkey) {
+            addresses.push(meta.pubkey);
+        }
+    }
+    addresses
+}
+
+/// A v0 message running `instructions` under a `compute_units` limit, with the
+/// accounts found in `lookup_tables` referenced through them.
+pub fn message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    compute_units: u32,
+    lookup_tables: &[AddressLookupTableAccount],
+    blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    let mut budgeted = vec![ComputeBudgetInstruction::set_compute_unit_limit(compute_units)];
+    budgeted.extend_from_slice(instructions);
+    let message = v0::Message::try_compile(payer, &budgeted, lookup_tables, blockhash)?;
+    Ok(VersionedMessage::V0(message))
+}
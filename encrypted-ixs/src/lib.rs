@@ -4,6 +4,9 @@ use arcis_imports::*;
 mod circuits {
     use arcis_imports::*;
 
+    // Must match `MAX_DECK_SIZE` in the program
+    const MAX_DECK_SIZE: usize = 20;
+
     pub struct InputValues {
         v1: u8,
         v2: u8,
@@ -16,13 +19,61 @@ mod circuits {
         pub no_cards1: u8,
         pub no_cards2: u8,
         pub no_cards3: u8,
+        pub yes_choice: u8,         // selected slot for the current turn, 0 = not yet played
+        pub no_choice: u8,          // selected slot for the current turn, 0 = not yet played
+        pub yes_power1: u8,
+        pub yes_power2: u8,
+        pub yes_power3: u8,
+        pub yes_element1: u8,
+        pub yes_element2: u8,
+        pub yes_element3: u8,
+        pub no_power1: u8,
+        pub no_power2: u8,
+        pub no_power3: u8,
+        pub no_element1: u8,
+        pub no_element2: u8,
+        pub no_element3: u8,
+        pub yes_joined: bool,
+        pub no_joined: bool,
+        pub yes_round1: u8,         // slot played in each round, 0 = not played
+        pub yes_round2: u8,
+        pub yes_round3: u8,
+        pub no_round1: u8,
+        pub no_round2: u8,
+        pub no_round3: u8,
+        pub yes_used: u8,           // slots already played, bit 0 = slot 1 .. bit 2 = slot 3
+        pub no_used: u8,
+    }
+
+    // What one player may see of the game: their own hand and their score in the
+    // round decided by the computation, 0 while the round is still open
+    pub struct PlayerView {
+        pub card1: u8,
+        pub card2: u8,
+        pub card3: u8,
+        pub power1: u8,
+        pub power2: u8,
+        pub power3: u8,
+        pub element1: u8,
+        pub element2: u8,
+        pub element3: u8,
+        pub choice: u8,
+        pub round_score: u16,
     }
 
     pub struct PlayerJoin {      // 0 = yes, 1 = no
-        pub player_cards1: u8,      // encrypted card id
-        pub player_cards2: u8,      // encrypted card id
-        pub player_cards3: u8,      // encrypted card id
+        pub player_cards1: u8,      // encrypted deck index
+        pub player_cards2: u8,      // encrypted deck index
+        pub player_cards3: u8,      // encrypted deck index
+    }
+
+    // Plaintext stats table of the deck pool the player joins with, padded with zeros
+    pub struct DeckStats {
+        pub powers: [u8; MAX_DECK_SIZE],
+        pub elements: [u8; MAX_DECK_SIZE],
+        pub size: u8,
     }
+
     #[instruction]
     pub fn add_together(input_ctxt: Enc<Shared, InputValues>) -> Enc<Shared, u16> {
         let input = input_ctxt.to_arcis();
@@ -30,8 +81,11 @@ mod circuits {
         input_ctxt.owner.from_arcis(sum)
     }
 
+    // Every game circuit echoes its `computation_offset` so the callback can
+    // check the output against the computation queued on the game account.
+
     #[instruction]
-    pub fn init_game(mxe: Mxe) -> Enc<Mxe, GameMoves> {
+    pub fn init_game(mxe: Mxe, computation_offset: u64) -> (Enc<Mxe, GameMoves>, u64) {
         let game_moves = GameMoves {
             yes_cards1: 0,
             yes_cards2: 0,
@@ -39,43 +93,410 @@ mod circuits {
             no_cards1: 0,
             no_cards2: 0,
             no_cards3: 0,
+            yes_choice: 0,
+            no_choice: 0,
+            yes_power1: 0,
+            yes_power2: 0,
+            yes_power3: 0,
+            yes_element1: 0,
+            yes_element2: 0,
+            yes_element3: 0,
+            no_power1: 0,
+            no_power2: 0,
+            no_power3: 0,
+            no_element1: 0,
+            no_element2: 0,
+            no_element3: 0,
+            yes_joined: false,
+            no_joined: false,
+            yes_round1: 0,
+            yes_round2: 0,
+            yes_round3: 0,
+            no_round1: 0,
+            no_round2: 0,
+            no_round3: 0,
+            yes_used: 0,
+            no_used: 0,
         };
 
         // Encrypt the initial state for Arcium
-        mxe.from_arcis(game_moves)
+        (mxe.from_arcis(game_moves), computation_offset)
     }
 
+    // Oblivious lookup of a deck entry: every entry is visited so the index stays hidden
+    fn deck_entry(table: [u8; MAX_DECK_SIZE], index: u8) -> u8 {
+        let mut value = 0;
+        for i in 0..MAX_DECK_SIZE {
+            if index == i as u8 {
+                value = table[i];
+            }
+        }
+        value
+    }
+
+    fn pick(yes: bool, visible: bool, yes_value: u8, no_value: u8) -> u8 {
+        let value = if yes { yes_value } else { no_value };
+        if visible {
+            value
+        } else {
+            0
+        }
+    }
+
+    // View of `side`'s hand; every field is zeroed unless `visible`
+    fn side_view(state: &GameMoves, side: u8, round_score: u16, visible: bool) -> PlayerView {
+        let yes = side == 0;
+        PlayerView {
+            card1: pick(yes, visible, state.yes_cards1, state.no_cards1),
+            card2: pick(yes, visible, state.yes_cards2, state.no_cards2),
+            card3: pick(yes, visible, state.yes_cards3, state.no_cards3),
+            power1: pick(yes, visible, state.yes_power1, state.no_power1),
+            power2: pick(yes, visible, state.yes_power2, state.no_power2),
+            power3: pick(yes, visible, state.yes_power3, state.no_power3),
+            element1: pick(yes, visible, state.yes_element1, state.no_element1),
+            element2: pick(yes, visible, state.yes_element2, state.no_element2),
+            element3: pick(yes, visible, state.yes_element3, state.no_element3),
+            choice: pick(yes, visible, state.yes_choice, state.no_choice),
+            round_score: if visible { round_score } else { 0 },
+        }
+    }
+
+    fn side_free(state: &GameMoves, side: u8) -> bool {
+        if side == 0 {
+            !state.yes_joined
+        } else {
+            !state.no_joined
+        }
+    }
+
+    // Writes the three deck entries and their stats to `side` when `accepted`
+    fn seat(
+        mut state: GameMoves,
+        accepted: bool,
+        side: u8,
+        card1: u8,
+        card2: u8,
+        card3: u8,
+        deck: &DeckStats,
+    ) -> GameMoves {
+        let power1 = deck_entry(deck.powers, card1);
+        let power2 = deck_entry(deck.powers, card2);
+        let power3 = deck_entry(deck.powers, card3);
+        let element1 = deck_entry(deck.elements, card1);
+        let element2 = deck_entry(deck.elements, card2);
+        let element3 = deck_entry(deck.elements, card3);
+
+        if accepted {
+            if side == 0 {
+                state.yes_cards1 = card1;
+                state.yes_cards2 = card2;
+                state.yes_cards3 = card3;
+                state.yes_power1 = power1;
+                state.yes_power2 = power2;
+                state.yes_power3 = power3;
+                state.yes_element1 = element1;
+                state.yes_element2 = element2;
+                state.yes_element3 = element3;
+                state.yes_joined = true;
+            } else {
+                state.no_cards1 = card1;
+                state.no_cards2 = card2;
+                state.no_cards3 = card3;
+                state.no_power1 = power1;
+                state.no_power2 = power2;
+                state.no_power3 = power3;
+                state.no_element1 = element1;
+                state.no_element2 = element2;
+                state.no_element3 = element3;
+                state.no_joined = true;
+            }
+        }
+        state
+    }
+
+    /// Joins a side with three cards of the player's deck. The join is only
+    /// accepted when the side is still free, every index points into the deck and
+    /// the three are distinct. Only the outcome and the side are revealed, so the
+    /// program can roll back a refused join. The joining player gets a view of
+    /// their hand encrypted to their own key, empty when the join is refused.
     #[instruction]
     pub fn join_game(
         player_join_ctxt: Enc<Shared, PlayerJoin>,
-        game_ctxt: Enc<Mxe, GameMoves>,        
-        player_side: u8,                      
-    ) -> Enc<Mxe, GameMoves> {
+        game_ctxt: Enc<Mxe, GameMoves>,
+        player_side: u8,
+        deck: DeckStats,
+        computation_offset: u64,
+    ) -> (Enc<Mxe, GameMoves>, bool, u8, Enc<Shared, PlayerView>, u64) {
         let player_join = player_join_ctxt.to_arcis();
         let mut game_state = game_ctxt.to_arcis();
 
-    if player_side == 0 {
-        if game_state.yes_cards1 == 0
-            && game_state.yes_cards2 == 0
-            && game_state.yes_cards3 == 0
-        {
-            game_state.yes_cards1 = player_join.player_cards1;
-            game_state.yes_cards2 = player_join.player_cards2;
-            game_state.yes_cards3 = player_join.player_cards3;
+        let card1 = player_join.player_cards1;
+        let card2 = player_join.player_cards2;
+        let card3 = player_join.player_cards3;
+        let in_deck = card1 < deck.size && card2 < deck.size && card3 < deck.size;
+        let distinct = card1 != card2 && card1 != card3 && card2 != card3;
+        let accepted = in_deck && distinct && side_free(&game_state, player_side);
+        game_state = seat(game_state, accepted, player_side, card1, card2, card3, &deck);
+
+        let view = side_view(&game_state, player_side, 0, accepted);
+
+        // Re-encrypt the updated game state
+        (
+            game_ctxt.owner.from_arcis(game_state),
+            accepted.reveal(),
+            player_side,
+            player_join_ctxt.owner.from_arcis(view),
+            computation_offset,
+        )
+    }
+
+    /// Joins a side with a hand dealt by the MXE instead of chosen by the player.
+    /// The deck order is shuffled with Arcis randomness and the first three
+    /// entries that fall inside the deck are dealt, so nobody, the player
+    /// included, picks the cards. The player sees the hand through a view
+    /// encrypted to `viewer`.
+    #[instruction]
+    pub fn deal_hand(
+        viewer: Shared,
+        game_ctxt: Enc<Mxe, GameMoves>,
+        player_side: u8,
+        deck: DeckStats,
+        computation_offset: u64,
+    ) -> (Enc<Mxe, GameMoves>, bool, u8, Enc<Shared, PlayerView>, u64) {
+        let mut game_state = game_ctxt.to_arcis();
+
+        let mut order = [0u8; MAX_DECK_SIZE];
+        for i in 0..MAX_DECK_SIZE {
+            order[i] = i as u8;
         }
-    } else {
-        if game_state.no_cards1 == 0
-            && game_state.no_cards2 == 0
-            && game_state.no_cards3 == 0
-        {
-            game_state.no_cards1 = player_join.player_cards1;
-            game_state.no_cards2 = player_join.player_cards2;
-            game_state.no_cards3 = player_join.player_cards3;
+        ArcisRNG::shuffle(&mut order);
+
+        let mut cards = [0u8; 3];
+        let mut dealt = 0u8;
+        for i in 0..MAX_DECK_SIZE {
+            let index = order[i];
+            let in_deck = index < deck.size;
+            for slot in 0..3 {
+                if in_deck && dealt == slot as u8 {
+                    cards[slot] = index;
+                }
+            }
+            if in_deck {
+                dealt += 1;
+            }
         }
+
+        let accepted = dealt >= 3 && side_free(&game_state, player_side);
+        game_state = seat(game_state, accepted, player_side, cards[0], cards[1], cards[2], &deck);
+        let view = side_view(&game_state, player_side, 0, accepted);
+
+        (
+            game_ctxt.owner.from_arcis(game_state),
+            accepted.reveal(),
+            player_side,
+            viewer.from_arcis(view),
+            computation_offset,
+        )
     }
 
-        // Re-encrypt the updated game state
-        game_ctxt.owner.from_arcis(game_state)
+    fn card_in_slot(slot: u8, card1: u8, card2: u8, card3: u8) -> u8 {
+        if slot == 1 {
+            card1
+        } else if slot == 2 {
+            card2
+        } else {
+            card3
+        }
+    }
+
+    fn slot_bit(slot: u8) -> u8 {
+        if slot == 1 {
+            1
+        } else if slot == 2 {
+            2
+        } else {
+            4
+        }
+    }
+
+    // A slot already played this game is swapped for the first one still unused,
+    // so each card of the hand is played exactly once over the three rounds
+    fn unused_slot(slot: u8, used: u8) -> u8 {
+        if used & slot_bit(slot) == 0 {
+            slot
+        } else if used & 1 == 0 {
+            1
+        } else if used & 2 == 0 {
+            2
+        } else {
+            3
+        }
+    }
+
+    // Elements: 0 = none, 1 = fire, 2 = water, 3 = earth.
+    // Water beats fire, fire beats earth, earth beats water.
+    fn element_beats(attacker: u8, defender: u8) -> bool {
+        (attacker == 2 && defender == 1)
+            || (attacker == 1 && defender == 3)
+            || (attacker == 3 && defender == 2)
+    }
+
+    // Power counts double, with another half on top when the element has the advantage
+    fn battle_score(power: u8, element: u8, opponent_element: u8) -> u16 {
+        let base = power as u16 * 2;
+        if element_beats(element, opponent_element) {
+            base + power as u16
+        } else {
+            base
+        }
+    }
+
+    /// Records a player's secret card selection for the current turn. Once both
+    /// sides have selected, the round is decided on the cards' stats and only the
+    /// winner is revealed: 0 = waiting for the other player, 1 = yes, 2 = no, 3 = draw.
+    /// A slot the player already used in an earlier round is replaced by their
+    /// first unused one. Each player also gets a view of their own hand and round score, encrypted
+    /// to the key they registered when joining.
+    #[instruction]
+    pub fn play_turn(
+        choice_ctxt: Enc<Shared, u8>,
+        game_ctxt: Enc<Mxe, GameMoves>,
+        player_side: u8,
+        current_turn: u8,
+        yes_viewer: Shared,
+        no_viewer: Shared,
+        computation_offset: u64,
+    ) -> (
+        Enc<Mxe, GameMoves>,
+        u8,
+        Enc<Shared, PlayerView>,
+        Enc<Shared, PlayerView>,
+        u64,
+    ) {
+        let choice = choice_ctxt.to_arcis();
+        let mut game_state = game_ctxt.to_arcis();
+
+        // Card index 0, 1 or 2; anything out of range plays the third card
+        let slot = if choice == 0 {
+            1
+        } else if choice == 1 {
+            2
+        } else {
+            3
+        };
+
+        if player_side == 0 {
+            game_state.yes_choice = unused_slot(slot, game_state.yes_used);
+        } else {
+            game_state.no_choice = unused_slot(slot, game_state.no_used);
+        }
+
+        let mut round_winner = 0;
+        let mut yes_score = 0;
+        let mut no_score = 0;
+        if game_state.yes_choice != 0 && game_state.no_choice != 0 {
+            let yes_power = card_in_slot(
+                game_state.yes_choice,
+                game_state.yes_power1,
+                game_state.yes_power2,
+                game_state.yes_power3,
+            );
+            let yes_element = card_in_slot(
+                game_state.yes_choice,
+                game_state.yes_element1,
+                game_state.yes_element2,
+                game_state.yes_element3,
+            );
+            let no_power = card_in_slot(
+                game_state.no_choice,
+                game_state.no_power1,
+                game_state.no_power2,
+                game_state.no_power3,
+            );
+            let no_element = card_in_slot(
+                game_state.no_choice,
+                game_state.no_element1,
+                game_state.no_element2,
+                game_state.no_element3,
+            );
+
+            yes_score = battle_score(yes_power, yes_element, no_element);
+            no_score = battle_score(no_power, no_element, yes_element);
+
+            round_winner = if yes_score > no_score {
+                1
+            } else if no_score > yes_score {
+                2
+            } else {
+                3
+            };
+
+            // Keep what was played for the post-game reveal
+            if current_turn == 0 {
+                game_state.yes_round1 = game_state.yes_choice;
+                game_state.no_round1 = game_state.no_choice;
+            } else if current_turn == 1 {
+                game_state.yes_round2 = game_state.yes_choice;
+                game_state.no_round2 = game_state.no_choice;
+            } else {
+                game_state.yes_round3 = game_state.yes_choice;
+                game_state.no_round3 = game_state.no_choice;
+            }
+            game_state.yes_used = game_state.yes_used | slot_bit(game_state.yes_choice);
+            game_state.no_used = game_state.no_used | slot_bit(game_state.no_choice);
+
+            game_state.yes_choice = 0;
+            game_state.no_choice = 0;
+        }
+
+        let yes_view = side_view(&game_state, 0, yes_score, true);
+        let no_view = side_view(&game_state, 1, no_score, true);
+
+        (
+            game_ctxt.owner.from_arcis(game_state),
+            round_winner.reveal(),
+            yes_viewer.from_arcis(yes_view),
+            no_viewer.from_arcis(no_view),
+            computation_offset,
+        )
     }
 
+    /// Reveals both hands, their stats and the slot each side played in every
+    /// round, in the order of `GameHistory`: yes cards, no cards, yes powers,
+    /// yes elements, no powers, no elements, yes rounds, no rounds. The program
+    /// only queues it once the game has a result.
+    #[instruction]
+    pub fn reveal_game(
+        game_ctxt: Enc<Mxe, GameMoves>,
+        computation_offset: u64,
+    ) -> ([u8; 24], u64) {
+        let g = game_ctxt.to_arcis();
+        let record = [
+            g.yes_cards1,
+            g.yes_cards2,
+            g.yes_cards3,
+            g.no_cards1,
+            g.no_cards2,
+            g.no_cards3,
+            g.yes_power1,
+            g.yes_power2,
+            g.yes_power3,
+            g.yes_element1,
+            g.yes_element2,
+            g.yes_element3,
+            g.no_power1,
+            g.no_power2,
+            g.no_power3,
+            g.no_element1,
+            g.no_element2,
+            g.no_element3,
+            g.yes_round1,
+            g.yes_round2,
+            g.yes_round3,
+            g.no_round1,
+            g.no_round2,
+            g.no_round3,
+        ];
+        (record.reveal(), computation_offset)
+    }
 }
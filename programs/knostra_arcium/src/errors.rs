@@ -65,5 +65,107 @@ pub enum CustomError {
     PlayerAlreadyJoined,
 
     #[msg("Invalid Player")]
-    InvalidPayer
+    InvalidPayer,
+
+    #[msg("Bet does not belong to the game's market")]
+    GameMarketMismatch,
+
+    #[msg("Deck is not owned by the player")]
+    InvalidDeckOwner,
+
+    #[msg("Both players must join before playing")]
+    GameNotReady,
+
+    #[msg("Game has already finished")]
+    GameFinished,
+
+    #[msg("Player has already played this turn")]
+    TurnAlreadyPlayed,
+
+    #[msg("Card attributes are missing or invalid")]
+    InvalidCardAttributes,
+
+    #[msg("Cards must be three distinct entries of the deck")]
+    InvalidCardSelection,
+
+    #[msg("Market is not resolved this way")]
+    ResolutionModeMismatch,
+
+    #[msg("Game deadline has passed")]
+    GameExpired,
+
+    #[msg("Game deadline has not passed yet")]
+    DeadlineNotReached,
+
+    #[msg("Another computation is pending for this game")]
+    ComputationPending,
+
+    #[msg("Computation can neither be retried nor rolled back")]
+    ComputationNotRecoverable,
+
+    #[msg("Callback does not match the computation queued on this game")]
+    CallbackMismatch,
+
+    #[msg("Computation offset does not match the game step")]
+    InvalidComputationOffset,
+
+    #[msg("Game has no result yet")]
+    GameNotFinished,
+
+    #[msg("Game has already been revealed")]
+    GameAlreadyRevealed,
+
+    #[msg("Game does not deal cards this way")]
+    InvalidDealMode,
+
+    #[msg("Deck is locked in an active game")]
+    DeckLocked,
+
+    #[msg("Card index is outside the deck")]
+    InvalidCardIndex,
+
+    #[msg("Deck rules are out of bounds")]
+    InvalidDeckRules,
+
+    #[msg("Deck size is outside the allowed bounds")]
+    InvalidDeckSize,
+
+    #[msg("Deck already holds this card")]
+    DuplicateCard,
+
+    #[msg("Card is not from the approved collection")]
+    CollectionNotApproved,
+
+    #[msg("Card is frozen and can't be committed to a game")]
+    CardFrozen,
+
+    #[msg("Ante games need an ante card at join, other games take none")]
+    InvalidAnte,
+
+    #[msg("Side still has an ante in escrow")]
+    AnteOutstanding,
+
+    #[msg("No ante can be settled yet")]
+    NoAnteToSettle,
+
+    #[msg("Card standards don't line up with the cards")]
+    InvalidAssetStandard,
+
+    #[msg("Compressed card proof doesn't verify")]
+    InvalidCardProof,
+
+    #[msg("Deck pool must hold distinct cards of the deck, at most one game's worth")]
+    InvalidDeckPool,
+
+    #[msg("Only the market owner can do this")]
+    NotMarketOwner,
+
+    #[msg("Vault holds less than the treasury has booked")]
+    TreasuryShortfall,
+
+    #[msg("Only mpl-core cards can be played in a game")]
+    CardNotPlayable,
+
+    #[msg("A dealt hand is shuffled from a full pool of the deck")]
+    DealPoolTooSmall,
 }
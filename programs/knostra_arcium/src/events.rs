@@ -0,0 +1,192 @@
+use crate::state::{ComputationKind, GameAccount, ResolutionMode, Status};
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub treasury: Pubkey,
+    pub creator: Pubkey,
+    pub market_id: u64,
+    pub required_bet_amount: u64,
+    pub max_player_count: u64,
+    pub market_start: u64,
+    pub market_end: u64,
+    pub resolution_mode: ResolutionMode,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetPlaced {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub choice: bool,
+    pub total_amount: u64,
+    pub yes_count: u64,
+    pub no_count: u64,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketResolved {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub resolve_value: u64,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetClaimed {
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub payout: u64,
+    pub creator_fee: u64,
+    pub protocol_fee: u64,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketCancelled {
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesClaimed {
+    pub market: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckCreated {
+    pub deck: Pubkey,
+    pub owner: Pubkey,
+    pub card_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AnteEscrowed {
+    pub game: Pubkey,
+    pub side: u8,
+    pub asset: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AnteSettled {
+    pub game: Pubkey,
+    pub side: u8,
+    pub asset: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckUpdated {
+    pub deck: Pubkey,
+    pub owner: Pubkey,
+    pub card_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckReleased {
+    pub deck: Pubkey,
+    pub game: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeckClosed {
+    pub deck: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryDiscrepancy {
+    pub market: Pubkey,
+    pub expected_lamports: u64,
+    pub vault_lamports: u64,
+    pub frozen: bool,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketUnfrozen {
+    pub market: Pubkey,
+    pub vault_lamports: u64,
+    pub status: Status,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameForfeited {
+    pub game: Pubkey,
+    pub market: Pubkey,
+    pub turn: u8,
+    pub result: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TurnPlayed {
+    pub game: Pubkey,
+    pub market: Pubkey,
+    pub turn: u8,
+    pub round_winner: u8,
+    pub yes_wins: u8,
+    pub no_wins: u8,
+    pub result: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameJoined {
+    pub game: Pubkey,
+    pub market: Pubkey,
+    pub player: Pubkey,
+    pub side: u8,
+    pub accepted: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ComputationFailed {
+    pub game: Pubkey,
+    pub kind: ComputationKind,
+    pub offset: u64,
+    pub timed_out: bool,
+    pub timestamp: i64,
+}
+
+/// A player's own view of the game, encrypted to the key they joined with.
+#[event]
+pub struct PlayerViewUpdated {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub side: u8,
+    pub view: [[u8; 32]; GameAccount::PLAYER_VIEW_LEN],
+    pub nonce: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameRevealed {
+    pub game: Pubkey,
+    pub history: Pubkey,
+    pub result: u8,
+    pub timestamp: i64,
+}
@@ -0,0 +1,145 @@
+use crate::errors::CustomError;
+use crate::events::{AnteEscrowed, AnteSettled};
+use crate::instructions::{asset_collection, card_collection, deck_assets, CardLock};
+use crate::state::*;
+use anchor_lang::prelude::*;
+use mpl_core::instructions::TransferV1CpiBuilder;
+
+impl<'a, 'info> CardLock<'a, 'info> {
+    /// Moves the deck card at `index` from `payer` into escrow, owned by the
+    /// game account, as `side`'s ante. Only mpl-core cards can be staked. The
+    /// deck's cards are the remaining accounts, as for `freeze_deck`.
+    pub fn escrow_ante(
+        &self,
+        game: &mut Account<'info, GameAccount>,
+        deck: &DeckAccount,
+        side: u8,
+        index: u8,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            game.ante_stake_mut(side).asset == Pubkey::default(),
+            CustomError::AnteOutstanding
+        );
+        require!(
+            (index as usize) < deck.nfts.len(),
+            CustomError::InvalidCardIndex
+        );
+        let (assets, _) = deck_assets(deck, remaining_accounts)?;
+        let position = deck
+            .core_cards()
+            .position(|(i, _)| i == index as usize)
+            .ok_or(CustomError::InvalidAnte)?;
+        let asset_info = &assets[position];
+        let collection = card_collection(asset_info, remaining_accounts)?;
+
+        TransferV1CpiBuilder::new(self.mpl_core_program)
+            .asset(asset_info)
+            .collection(collection)
+            .payer(self.payer)
+            .authority(Some(self.payer))
+            .new_owner(&game.to_account_info())
+            .system_program(Some(self.system_program))
+            .invoke()?;
+
+        *game.ante_stake_mut(side) = AnteStake {
+            asset: asset_info.key(),
+            owner: self.payer.key(),
+        };
+
+        emit!(AnteEscrowed {
+            game: game.key(),
+            side,
+            asset: asset_info.key(),
+            owner: self.payer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Permissionless. For each side with an ante due (see
+/// `GameAccount::ante_recipient`), yes first, the remaining accounts are the
+/// asset, its collection if it has one, then the recipient.
+#[derive(Accounts)]
+pub struct SettleAnte<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: mpl-core program, checked by address
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleAnte<'info> {
+    fn settle_ante(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let game_info = self.game_account.to_account_info();
+        let market_key = self.game_account.market_account;
+        let bump = self.game_account.bump;
+        let signer_seeds: &[&[u8]] = &[b"game", market_key.as_ref(), &[bump]];
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        let mut accounts = remaining_accounts.iter();
+        let mut settled = 0;
+        for side in [0u8, 1] {
+            let Some(recipient) = self.game_account.ante_recipient(side) else {
+                continue;
+            };
+            let asset = self.game_account.ante_stake_mut(side).asset;
+
+            let asset_info = accounts.next().ok_or(CustomError::MissingAccount)?;
+            require_keys_eq!(*asset_info.key, asset, CustomError::InvalidMint);
+            let collection = match asset_collection(asset_info)? {
+                Some(collection) => {
+                    let collection_info = accounts.next().ok_or(CustomError::MissingAccount)?;
+                    require_keys_eq!(
+                        *collection_info.key,
+                        collection,
+                        CustomError::CollectionNotApproved
+                    );
+                    Some(collection_info)
+                }
+                None => None,
+            };
+            let recipient_info = accounts.next().ok_or(CustomError::MissingAccount)?;
+            require_keys_eq!(*recipient_info.key, recipient, CustomError::InvalidPayer);
+
+            TransferV1CpiBuilder::new(&self.mpl_core_program.to_account_info())
+                .asset(asset_info)
+                .collection(collection)
+                .payer(&self.payer.to_account_info())
+                .authority(Some(&game_info))
+                .new_owner(recipient_info)
+                .system_program(Some(&self.system_program.to_account_info()))
+                .invoke_signed(&[signer_seeds])?;
+
+            *self.game_account.ante_stake_mut(side) = AnteStake::default();
+            settled += 1;
+
+            emit!(AnteSettled {
+                game: game_info.key(),
+                side,
+                asset,
+                recipient,
+                timestamp,
+            });
+        }
+        require!(settled > 0, CustomError::NoAnteToSettle);
+
+        Ok(())
+    }
+}
+
+pub fn handle_settle_ante<'info>(ctx: Context<'_, '_, '_, 'info, SettleAnte<'info>>) -> Result<()> {
+    ctx.accounts.settle_ante(ctx.remaining_accounts)
+}
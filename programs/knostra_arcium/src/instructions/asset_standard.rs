@@ -0,0 +1,183 @@
+use crate::errors::CustomError;
+use crate::instructions::{check_owned_asset, read_card_stats};
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::{keccak, pubkey};
+use anchor_spl::token_interface::TokenAccount;
+use mpl_core::types::UpdateAuthority;
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::types::TokenStandard;
+
+pub const BUBBLEGUM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+pub const ACCOUNT_COMPRESSION_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+// Anchor discriminator of account compression's `verify_leaf`
+const VERIFY_LEAF_DISCRIMINATOR: [u8; 8] = [124, 220, 22, 223, 104, 10, 250, 224];
+
+/// Checks that `token_info` is a token account of `owner` holding the Token
+/// Metadata NFT `mint`, and returns the card's verified collection (default
+/// if none).
+pub fn check_owned_token(
+    token_info: &AccountInfo,
+    metadata_info: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Pubkey> {
+    require!(
+        *token_info.owner == anchor_spl::token::ID
+            || *token_info.owner == anchor_spl::token_2022::ID,
+        CustomError::InvalidTokenAccount
+    );
+    let token = TokenAccount::try_deserialize(&mut token_info.try_borrow_data()?.as_ref())
+        .map_err(|_| CustomError::InvalidTokenAccount)?;
+    require_keys_eq!(token.mint, *mint, CustomError::InvalidTokenAccount);
+    require_keys_eq!(token.owner, *owner, CustomError::NotNftOwner);
+    require!(token.amount == 1, CustomError::InvalidNftBalance);
+
+    require_keys_eq!(
+        *metadata_info.key,
+        Metadata::find_pda(mint).0,
+        CustomError::InvalidMint
+    );
+    require_keys_eq!(
+        *metadata_info.owner,
+        mpl_token_metadata::ID,
+        CustomError::InvalidMint
+    );
+    let metadata = Metadata::safe_deserialize(&metadata_info.try_borrow_data()?)
+        .map_err(|_| CustomError::InvalidMint)?;
+    require!(
+        matches!(
+            metadata.token_standard,
+            None | Some(
+                TokenStandard::NonFungible
+                    | TokenStandard::NonFungibleEdition
+                    | TokenStandard::ProgrammableNonFungible
+                    | TokenStandard::ProgrammableNonFungibleEdition
+            )
+        ),
+        CustomError::InvalidMint
+    );
+
+    Ok(match metadata.collection {
+        Some(collection) if collection.verified => collection.key,
+        _ => Pubkey::default(),
+    })
+}
+
+/// Checks, by CPI to account compression, that the Bubblegum asset `asset_id`
+/// is a leaf of `tree_info` owned by `owner`. The leaf's collection is hashed
+/// away, so compressed cards never count as part of one.
+pub fn check_owned_leaf<'info>(
+    compression_info: &AccountInfo<'info>,
+    tree_info: &AccountInfo<'info>,
+    proof_infos: &[AccountInfo<'info>],
+    asset_id: &Pubkey,
+    owner: &Pubkey,
+    proof: &CompressedProof,
+) -> Result<()> {
+    require_keys_eq!(
+        *compression_info.key,
+        ACCOUNT_COMPRESSION_ID,
+        CustomError::InvalidCardProof
+    );
+    require_keys_eq!(
+        *tree_info.owner,
+        ACCOUNT_COMPRESSION_ID,
+        CustomError::InvalidCardProof
+    );
+    let nonce = proof.nonce.to_le_bytes();
+    let (id, _) =
+        Pubkey::find_program_address(&[b"asset", tree_info.key.as_ref(), &nonce], &BUBBLEGUM_ID);
+    require_keys_eq!(id, *asset_id, CustomError::InvalidMint);
+
+    // Bubblegum's LeafSchema::V1 hash, so the leaf only matches with `owner`
+    let leaf = keccak::hashv(&[
+        &[1],
+        id.as_ref(),
+        owner.as_ref(),
+        proof.delegate.as_ref(),
+        &nonce,
+        &proof.data_hash,
+        &proof.creator_hash,
+    ]);
+
+    let mut data = VERIFY_LEAF_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&proof.root);
+    data.extend_from_slice(&leaf.to_bytes());
+    data.extend_from_slice(&proof.index.to_le_bytes());
+
+    let mut accounts = vec![AccountMeta::new_readonly(*tree_info.key, false)];
+    accounts.extend(
+        proof_infos
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node.key, false)),
+    );
+    let mut account_infos = vec![tree_info.clone()];
+    account_infos.extend_from_slice(proof_infos);
+    account_infos.push(compression_info.clone());
+
+    invoke(
+        &Instruction {
+            program_id: ACCOUNT_COMPRESSION_ID,
+            accounts,
+            data,
+        },
+        &account_infos,
+    )?;
+    Ok(())
+}
+
+/// Takes the next `count` accounts off `accounts`.
+fn take_accounts<'a, 'info>(
+    accounts: &mut &'a [AccountInfo<'info>],
+    count: usize,
+) -> Result<&'a [AccountInfo<'info>]> {
+    require!(accounts.len() >= count, CustomError::MissingAccount);
+    let (taken, rest) = accounts.split_at(count);
+    *accounts = rest;
+    Ok(taken)
+}
+
+/// Checks that `owner` holds the card `mint` under `standard`, and returns
+/// the card's collection (default if none) and its stats. Only mpl-core cards
+/// carry stats on chain; Token Metadata and compressed cards keep theirs in
+/// off-chain JSON and can't be frozen, so they hold default stats and stay out
+/// of games (see `check_deck_owned`). The card's accounts are taken off the
+/// front of `accounts`:
+/// - mpl-core: the asset
+/// - Token Metadata: the owner's token account, then the metadata account
+/// - compressed: the account compression program, the tree, then the
+///   `proof_len` proof nodes of the next of `proofs`
+pub fn read_held_card<'a, 'b, 'info>(
+    accounts: &mut &'a [AccountInfo<'info>],
+    proofs: &mut impl Iterator<Item = &'b CompressedProof>,
+    mint: &Pubkey,
+    standard: AssetStandard,
+    owner: &Pubkey,
+) -> Result<(CardStats, Pubkey)> {
+    match standard {
+        AssetStandard::Core => {
+            let asset_info = &take_accounts(accounts, 1)?[0];
+            let asset = check_owned_asset(asset_info, mint, owner)?;
+            let collection = match asset.update_authority {
+                UpdateAuthority::Collection(collection) => collection,
+                _ => Pubkey::default(),
+            };
+            Ok((read_card_stats(asset_info)?, collection))
+        }
+        AssetStandard::TokenMetadata => {
+            let infos = take_accounts(accounts, 2)?;
+            let collection = check_owned_token(&infos[0], &infos[1], mint, owner)?;
+            Ok((CardStats::default(), collection))
+        }
+        AssetStandard::Compressed => {
+            let proof = proofs.next().ok_or(CustomError::InvalidCardProof)?;
+            let infos = take_accounts(accounts, 2 + proof.proof_len as usize)?;
+            check_owned_leaf(&infos[0], &infos[1], &infos[2..], mint, owner, proof)?;
+            Ok((CardStats::default(), Pubkey::default()))
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::errors::CustomError;
+use crate::events::BetPlaced;
 use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
@@ -98,6 +99,18 @@ impl<'info> PlaceBet<'info> {
             treasury_account.status = Status::Ongoing;
         }
 
+        emit!(BetPlaced {
+            market: market_account.key(),
+            user: self.user.key(),
+            amount: bet_amount,
+            choice,
+            total_amount: treasury_account.total_amount,
+            yes_count: treasury_account.yes_count,
+            no_count: treasury_account.no_count,
+            status: market_account.status,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 }
@@ -1,4 +1,5 @@
 use crate::errors::CustomError;
+use crate::events::MarketCancelled;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -51,6 +52,13 @@ impl<'info> CancelMarket<'info> {
         market_account.status = Status::Cancelled;
         treasury_account.status = Status::Cancelled;
 
+        emit!(MarketCancelled {
+            market: market_account.key(),
+            creator: self.creator.key(),
+            status: market_account.status,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 }
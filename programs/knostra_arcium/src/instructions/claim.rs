@@ -1,4 +1,5 @@
 use crate::errors::CustomError;
+use crate::events::BetClaimed;
 use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
@@ -80,8 +81,23 @@ impl<'info> Claim<'info> {
 
             transfer(cpi_ctx, bet_account.bet_amount)?;
 
+            treasury_account.total_amount = treasury_account
+                .total_amount
+                .checked_sub(bet_account.bet_amount)
+                .ok_or(CustomError::MathOverflow)?;
+
             bet_account.claimed = true;
 
+            emit!(BetClaimed {
+                market: market_key,
+                user: self.user.key(),
+                payout: bet_account.bet_amount,
+                creator_fee: 0,
+                protocol_fee: 0,
+                status: market_account.status,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+
             return Ok(());
         }
 
@@ -96,24 +112,25 @@ impl<'info> Claim<'info> {
 
             // TODO: Make fee configurable
             // Deduct platform fees
-            let total_fee_bps: u64 = 200; // 2%
             let creator_fee_bps: u64 = 100; // 1%
             let protocol_fee_bps: u64 = 100; // 1%
 
-            let total_fee = payout
-                .checked_mul(total_fee_bps)
+            let creator_fee = payout
+                .checked_mul(creator_fee_bps)
                 .ok_or(CustomError::MathOverflow)?
                 .checked_div(10_000)
                 .ok_or(CustomError::MathOverflow)?;
 
-            let creator_fee = payout
-                .checked_mul(creator_fee_bps)
+            let protocol_fee = payout
+                .checked_mul(protocol_fee_bps)
                 .ok_or(CustomError::MathOverflow)?
                 .checked_div(10_000)
                 .ok_or(CustomError::MathOverflow)?;
 
-            let protocol_fee = total_fee
-                .checked_sub(protocol_fee_bps)
+            // Derived from its parts so every fee lamport left in the vault is booked
+            // to one of the fee counters
+            let total_fee = creator_fee
+                .checked_add(protocol_fee)
                 .ok_or(CustomError::MathOverflow)?;
 
             payout = payout
@@ -158,6 +175,16 @@ impl<'info> Claim<'info> {
                 .ok_or(CustomError::MathOverflow)?;
 
             bet_account.claimed = true;
+
+            emit!(BetClaimed {
+                market: market_key,
+                user: self.user.key(),
+                payout,
+                creator_fee,
+                protocol_fee,
+                status: market_account.status,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
         } else {
             return err!(CustomError::NotAWinner);
         }
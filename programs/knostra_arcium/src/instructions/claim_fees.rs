@@ -1,4 +1,5 @@
 use crate::errors::CustomError;
+use crate::events::FeesClaimed;
 use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
@@ -47,7 +48,10 @@ impl<'info> ClaimFees<'info> {
             treasury_account.creator == self.user.key(),
             CustomError::Unauthorized
         );
-        require!(treasury_account.fee_amount > 0, CustomError::NoFeesToClaim);
+        require!(
+            treasury_account.creator_fee_amount > 0,
+            CustomError::NoFeesToClaim
+        );
 
         let creator_fee = treasury_account
             .creator_fee_amount
@@ -89,6 +93,14 @@ impl<'info> ClaimFees<'info> {
         //treasury_account.fee_amount = 0;
         treasury_account.creator_fee_amount = 0;
 
+        emit!(FeesClaimed {
+            market: market_key,
+            creator: self.user.key(),
+            amount: creator_fee,
+            status: market_account.status,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 }
@@ -0,0 +1,35 @@
+use crate::errors::CustomError;
+use crate::events::DeckClosed;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseDeck<'info> {
+    #[account(
+        mut,
+        has_one = owner @ CustomError::InvalidDeckOwner,
+        close = owner,
+    )]
+    pub deck_account: Account<'info, DeckAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+impl<'info> CloseDeck<'info> {
+    fn close(&mut self) -> Result<()> {
+        require!(!self.deck_account.is_locked(), CustomError::DeckLocked);
+
+        emit!(DeckClosed {
+            deck: self.deck_account.key(),
+            owner: self.owner.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handle_close_deck(ctx: Context<CloseDeck>) -> Result<()> {
+    ctx.accounts.close()
+}
@@ -1,3 +1,4 @@
+use crate::events::MarketCreated;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -42,6 +43,8 @@ impl<'info> CreateMarket<'info> {
         bump: u8,
         treasury_bump: u8,
     ) -> Result<()> {
+        params.deck_rules.validate()?;
+
         let clock = Clock::get()?;
         self.market_account.set_inner(MarketAccount {
             bump,
@@ -60,6 +63,8 @@ impl<'info> CreateMarket<'info> {
             updated_at: clock.unix_timestamp,
             resolve_value: 0,
             market_id: seed,
+            resolution_mode: params.resolution_mode,
+            deck_rules: params.deck_rules,
         });
 
         self.treasury_account.set_inner(TreasuryAccount {
@@ -72,7 +77,23 @@ impl<'info> CreateMarket<'info> {
             creator_fee_amount: 0,
             status: Status::NotStarted,
             creator: self.user.key(),
+            frozen_from: Status::NotStarted,
+        });
+
+        emit!(MarketCreated {
+            market: self.market_account.key(),
+            treasury: self.treasury_account.key(),
+            creator: self.user.key(),
+            market_id: seed,
+            required_bet_amount: self.market_account.required_bet_amount,
+            max_player_count: self.market_account.max_player_count,
+            market_start: self.market_account.market_start,
+            market_end: self.market_account.market_end,
+            resolution_mode: self.market_account.resolution_mode,
+            status: self.market_account.status,
+            timestamp: clock.unix_timestamp,
         });
+
         Ok(())
     }
 }
@@ -1,13 +1,137 @@
 use crate::errors::CustomError;
+use crate::events::DeckCreated;
+use crate::instructions::{deck_assets, read_held_card};
 use crate::state::*;
 use anchor_lang::prelude::*;
 use mpl_core::accounts::BaseAssetV1;
+use mpl_core::fetch_plugin;
+use mpl_core::types::{Attributes, Key as MplKey, PluginAuthority, PluginType};
+
+/// Reads `power`, `element` and `rarity` from the asset's Attributes plugin.
+/// Missing keys default to 0; values are numbers, or names for elements and rarities.
+/// Only attributes managed by the asset's update authority count, so a holder
+/// can't rewrite their own cards, and power is capped at `MAX_CARD_POWER`.
+pub fn read_card_stats(asset_info: &AccountInfo) -> Result<CardStats> {
+    let (authority, attributes, _) =
+        fetch_plugin::<BaseAssetV1, Attributes>(asset_info, PluginType::Attributes)
+            .map_err(|_| CustomError::InvalidCardAttributes)?;
+    require!(
+        authority == PluginAuthority::UpdateAuthority,
+        CustomError::InvalidCardAttributes
+    );
+
+    let mut stats = CardStats::default();
+    for attribute in attributes.attribute_list.iter() {
+        let value = attribute.value.trim().to_ascii_lowercase();
+        match attribute.key.trim().to_ascii_lowercase().as_str() {
+            "power" => {
+                stats.power = value
+                    .parse::<u8>()
+                    .map_err(|_| CustomError::InvalidCardAttributes)?
+                    .min(MAX_CARD_POWER);
+            }
+            "element" => {
+                stats.element = match value.as_str() {
+                    "none" => ELEMENT_NONE,
+                    "fire" => ELEMENT_FIRE,
+                    "water" => ELEMENT_WATER,
+                    "earth" => ELEMENT_EARTH,
+                    _ => value
+                        .parse()
+                        .ok()
+                        .filter(|element| *element <= ELEMENT_EARTH)
+                        .ok_or(CustomError::InvalidCardAttributes)?,
+                };
+            }
+            "rarity" => {
+                stats.rarity = match value.as_str() {
+                    "common" => 0,
+                    "uncommon" => 1,
+                    "rare" => 2,
+                    "epic" => 3,
+                    "legendary" => 4,
+                    _ => value
+                        .parse()
+                        .ok()
+                        .filter(|rarity| *rarity <= MAX_CARD_RARITY)
+                        .ok_or(CustomError::InvalidCardAttributes)?,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Checks that `asset_info` is the mpl-core asset `mint` owned by `owner`.
+pub fn check_owned_asset(
+    asset_info: &AccountInfo,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<BaseAssetV1> {
+    require_keys_eq!(*asset_info.key, *mint, CustomError::InvalidMint);
+    require_keys_eq!(*asset_info.owner, mpl_core::ID, CustomError::InvalidMint);
+
+    let data = asset_info.try_borrow_data()?;
+    let asset =
+        BaseAssetV1::deserialize(&mut data.as_ref()).map_err(|_| CustomError::InvalidMint)?;
+
+    require!(asset.key == MplKey::AssetV1, CustomError::InvalidMint);
+    require_keys_eq!(asset.owner, *owner, CustomError::NotNftOwner);
+
+    Ok(asset)
+}
+
+/// Checks that `deck` plays only mpl-core cards and that `owner` still holds
+/// every one of them. Which cards a player picks stays encrypted, so the whole
+/// deck is checked. The accounts are the deck's mpl-core accounts (see
+/// `deck_assets`).
+///
+/// Token Metadata and compressed cards can sit in a deck but never play: a
+/// join can't freeze them, and their stats live off chain.
+pub fn check_deck_owned<'info>(
+    deck: &DeckAccount,
+    owner: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    require!(
+        deck.standards
+            .iter()
+            .all(|standard| *standard == AssetStandard::Core),
+        CustomError::CardNotPlayable
+    );
+    let (assets, _) = deck_assets(deck, remaining_accounts)?;
+    for ((_, mint), asset_info) in deck.core_cards().zip(assets) {
+        check_owned_asset(asset_info, mint, owner)?;
+    }
+    Ok(())
+}
+
+/// One standard per mint, and one proof per compressed card.
+pub fn check_standards(
+    mints: &[Pubkey],
+    standards: &[AssetStandard],
+    proofs: &[CompressedProof],
+) -> Result<()> {
+    require!(
+        mints.len() == standards.len()
+            && proofs.len()
+                == standards
+                    .iter()
+                    .filter(|standard| **standard == AssetStandard::Compressed)
+                    .count(),
+        CustomError::InvalidAssetStandard
+    );
+    Ok(())
+}
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct CreateDeckAccount<'info> {
+    // A deck is created once; its cards change through the update instructions
     #[account(
-        init_if_needed,
+        init,
         payer = owner,
         space = 8 + DeckAccount::INIT_SPACE,
         seeds = [b"deck", owner.key().as_ref(), &seed.to_le_bytes()],
@@ -26,33 +150,48 @@ impl<'info> CreateDeckAccount<'info> {
         &mut self,
         _seed: u64,
         mints: Vec<Pubkey>,
+        standards: Vec<AssetStandard>,
+        proofs: Vec<CompressedProof>,
         bump: u8,
         remaining_accounts: &[AccountInfo<'info>], // 👈 lifetime must match
     ) -> Result<()> {
         let deck_account = &mut self.deck_account;
 
+        DeckRules::DEFAULT.check_size(mints.len())?;
+        check_standards(&mints, &standards, &proofs)?;
+
         deck_account.set_inner(DeckAccount {
             owner: self.owner.key(),
             bump,
             nfts: Vec::new(),
+            stats: Vec::new(),
+            locked_game: Pubkey::default(),
+            collection: Pubkey::default(),
+            standards: Vec::new(),
+            locked_pool: vec![],
         });
 
-        for (i, mint) in mints.iter().enumerate() {
-            let asset_info = &remaining_accounts[i];
-
-            require_keys_eq!(*asset_info.key, *mint, CustomError::InvalidMint);
-
-            let data = asset_info.try_borrow_data()?;
-            let asset = BaseAssetV1::deserialize(&mut data.as_ref())
-                .map_err(|_| CustomError::InvalidMint)?;
-
-            // require_eq!(asset.key, MplKey::AssetV1, CustomError::InvalidMint);
-            
-            require_keys_eq!(asset.owner, self.owner.key(), CustomError::NotNftOwner);
-
-            deck_account.nfts.push(*mint);
+        // Each card's accounts follow the previous card's
+        let mut accounts = remaining_accounts;
+        let mut proofs = proofs.iter();
+        for (mint, standard) in mints.iter().zip(standards) {
+            let (stats, collection) = read_held_card(
+                &mut accounts,
+                &mut proofs,
+                mint,
+                standard,
+                &self.owner.key(),
+            )?;
+            deck_account.push_card(*mint, standard, stats, collection)?;
         }
 
+        emit!(DeckCreated {
+            deck: deck_account.key(),
+            owner: self.owner.key(),
+            card_count: deck_account.nfts.len() as u64,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 }
@@ -61,8 +200,16 @@ pub fn handle_create_deck<'info>(
     ctx: Context<'_, '_, '_, 'info, CreateDeckAccount<'info>>,
     seed: u64,
     mints: Vec<Pubkey>,
+    standards: Vec<AssetStandard>,
+    proofs: Vec<CompressedProof>,
     bump: u8,
 ) -> Result<()> {
-    ctx.accounts
-        .create_deck_account(seed, mints, bump, &ctx.remaining_accounts)
+    ctx.accounts.create_deck_account(
+        seed,
+        mints,
+        standards,
+        proofs,
+        bump,
+        &ctx.remaining_accounts,
+    )
 }
@@ -0,0 +1,83 @@
+use crate::errors::CustomError;
+use crate::events::GameForfeited;
+use crate::instructions::settle_from_game;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ClaimForfeit<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+
+    #[account(
+        mut,
+        address = game_account.market_account,
+    )]
+    pub market_account: Box<Account<'info, MarketAccount>>,
+
+    #[account(
+        mut,
+        has_one = market_account,
+        seeds = [b"treasury", market_account.key().as_ref()],
+        bump = treasury_account.bump,
+    )]
+    pub treasury_account: Box<Account<'info, TreasuryAccount>>,
+}
+
+impl<'info> ClaimForfeit<'info> {
+    /// Ends a game whose deadline has passed. Permissionless, so either player or
+    /// a crank can call it. A side that never joined, or that did not play while
+    /// the other did, loses the game; a game nobody joined is cancelled, and a
+    /// turn neither side played ends the game on the rounds won so far.
+    ///
+    /// A missed turn forfeits the whole game rather than the round: the card
+    /// the other side played is held in the MXE state, and the next round can
+    /// only start from a turn the MXE resolved.
+    fn claim_forfeit(&mut self) -> Result<()> {
+        let game = &mut self.game_account;
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        require!(game.result == ROUND_PENDING, CustomError::GameFinished);
+        require!(timestamp > game.deadline, CustomError::DeadlineNotReached);
+        require!(
+            game.computation.status != ComputationStatus::Pending,
+            CustomError::ComputationPending
+        );
+
+        let yes_joined = game.player_yes != Pubkey::default();
+        let no_joined = game.player_no != Pubkey::default();
+
+        game.result = match (yes_joined, no_joined) {
+            (false, false) => GAME_CANCELLED,
+            (true, false) => ROUND_YES,
+            (false, true) => ROUND_NO,
+            (true, true) => match (game.yes_played, game.no_played) {
+                (true, false) => ROUND_YES,
+                (false, true) => ROUND_NO,
+                _ if game.yes_wins > game.no_wins => ROUND_YES,
+                _ if game.no_wins > game.yes_wins => ROUND_NO,
+                _ => ROUND_DRAW,
+            },
+        };
+
+        settle_from_game(&mut self.market_account, &mut self.treasury_account, game.result)?;
+
+        emit!(GameForfeited {
+            game: game.key(),
+            market: game.market_account,
+            turn: game.current_turn,
+            result: game.result,
+            timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handle_claim_forfeit(ctx: Context<ClaimForfeit>) -> Result<()> {
+    ctx.accounts.claim_forfeit()
+}
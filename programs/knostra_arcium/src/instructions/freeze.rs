@@ -0,0 +1,187 @@
+use crate::errors::CustomError;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use mpl_core::accounts::BaseAssetV1;
+use mpl_core::fetch_plugin;
+use mpl_core::instructions::{
+    AddPluginV1CpiBuilder, ApprovePluginAuthorityV1CpiBuilder, UpdatePluginV1CpiBuilder,
+};
+use mpl_core::types::{FreezeDelegate, Plugin, PluginAuthority, PluginType, UpdateAuthority};
+
+// PDA every deck card is frozen under while its deck is in a game
+pub const FREEZE_AUTHORITY_SEED: &[u8] = b"freeze_authority";
+
+/// Accounts the mpl-core plugin instructions run with.
+pub struct CardLock<'a, 'info> {
+    pub mpl_core_program: &'a AccountInfo<'info>,
+    pub freeze_authority: &'a AccountInfo<'info>,
+    pub freeze_authority_bump: u8,
+    pub payer: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+/// The deck's mpl-core asset accounts, in deck order, followed by its
+/// collection when the cards share one. Cards of other standards can't be
+/// frozen and have no account here. A deck whose cards don't share a
+/// collection is followed by the collection of each card in one instead (see
+/// `card_collection`).
+pub fn deck_assets<'a, 'info>(
+    deck: &DeckAccount,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(&'a [AccountInfo<'info>], Option<&'a AccountInfo<'info>>)> {
+    let count = deck.core_cards().count();
+    require!(
+        remaining_accounts.len() >= count,
+        CustomError::MissingAccount
+    );
+    for ((_, mint), asset_info) in deck.core_cards().zip(remaining_accounts) {
+        require_keys_eq!(*asset_info.key, *mint, CustomError::InvalidMint);
+    }
+
+    let collection = if deck.collection == Pubkey::default() {
+        None
+    } else {
+        let collection_info = remaining_accounts
+            .get(count)
+            .ok_or(CustomError::MissingAccount)?;
+        require_keys_eq!(
+            *collection_info.key,
+            deck.collection,
+            CustomError::CollectionNotApproved
+        );
+        Some(collection_info)
+    };
+
+    Ok((&remaining_accounts[..count], collection))
+}
+
+/// The collection `asset_info` belongs to, if any.
+pub fn asset_collection(asset_info: &AccountInfo) -> Result<Option<Pubkey>> {
+    let data = asset_info.try_borrow_data()?;
+    let asset =
+        BaseAssetV1::deserialize(&mut data.as_ref()).map_err(|_| CustomError::InvalidMint)?;
+    Ok(match asset.update_authority {
+        UpdateAuthority::Collection(collection) => Some(collection),
+        _ => None,
+    })
+}
+
+/// The account of the collection `asset_info` belongs to, if any, looked up
+/// among `accounts` by key, since mpl-core only changes a card in a
+/// collection with that collection's account.
+pub fn card_collection<'a, 'info>(
+    asset_info: &AccountInfo<'info>,
+    accounts: &'a [AccountInfo<'info>],
+) -> Result<Option<&'a AccountInfo<'info>>> {
+    let Some(collection) = asset_collection(asset_info)? else {
+        return Ok(None);
+    };
+    let collection_info = accounts
+        .iter()
+        .find(|info| *info.key == collection)
+        .ok_or(CustomError::MissingAccount)?;
+    Ok(Some(collection_info))
+}
+
+impl<'a, 'info> CardLock<'a, 'info> {
+    pub fn authority(&self) -> PluginAuthority {
+        PluginAuthority::Address {
+            address: self.freeze_authority.key(),
+        }
+    }
+
+    pub fn set_frozen(
+        &self,
+        asset_info: &AccountInfo<'info>,
+        collection: Option<&AccountInfo<'info>>,
+        frozen: bool,
+    ) -> Result<()> {
+        UpdatePluginV1CpiBuilder::new(self.mpl_core_program)
+            .asset(asset_info)
+            .collection(collection)
+            .payer(self.payer)
+            .authority(Some(self.freeze_authority))
+            .system_program(self.system_program)
+            .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen }))
+            .invoke_signed(&[&[FREEZE_AUTHORITY_SEED, &[self.freeze_authority_bump]]])?;
+        Ok(())
+    }
+
+    /// Freezes every mpl-core card of `deck` but the one at deck index
+    /// `except`, the ante that goes into escrow instead, under the program's
+    /// authority. `payer` must be the cards' owner. A card that is frozen
+    /// already, by anyone, is refused so it can't be committed to two games.
+    pub fn freeze_deck(
+        &self,
+        deck: &DeckAccount,
+        except: Option<u8>,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<()> {
+        let (assets, _) = deck_assets(deck, remaining_accounts)?;
+
+        for ((i, _), asset_info) in deck.core_cards().zip(assets) {
+            if except == Some(i as u8) {
+                continue;
+            }
+            let collection = card_collection(asset_info, remaining_accounts)?;
+            match fetch_plugin::<BaseAssetV1, FreezeDelegate>(
+                asset_info,
+                PluginType::FreezeDelegate,
+            ) {
+                Err(_) => {
+                    AddPluginV1CpiBuilder::new(self.mpl_core_program)
+                        .asset(asset_info)
+                        .collection(collection)
+                        .payer(self.payer)
+                        .authority(Some(self.payer))
+                        .system_program(self.system_program)
+                        .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: true }))
+                        .init_authority(self.authority())
+                        .invoke()?;
+                }
+                Ok((_, freeze, _)) if freeze.frozen => {
+                    return Err(CustomError::CardFrozen.into());
+                }
+                Ok((authority, _, _)) => {
+                    if authority != self.authority() {
+                        ApprovePluginAuthorityV1CpiBuilder::new(self.mpl_core_program)
+                            .asset(asset_info)
+                            .collection(collection)
+                            .payer(self.payer)
+                            .authority(Some(self.payer))
+                            .system_program(self.system_program)
+                            .plugin_type(PluginType::FreezeDelegate)
+                            .new_authority(self.authority())
+                            .invoke()?;
+                    }
+                    self.set_frozen(asset_info, collection, true)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Thaws the cards of `deck` this program froze. Cards it doesn't hold
+    /// frozen are left alone, so decks locked before freezing release too.
+    pub fn thaw_deck(
+        &self,
+        deck: &DeckAccount,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<()> {
+        let (assets, _) = deck_assets(deck, remaining_accounts)?;
+
+        for asset_info in assets {
+            if let Ok((authority, freeze, _)) =
+                fetch_plugin::<BaseAssetV1, FreezeDelegate>(asset_info, PluginType::FreezeDelegate)
+            {
+                if freeze.frozen && authority == self.authority() {
+                    let collection = card_collection(asset_info, remaining_accounts)?;
+                    self.set_frozen(asset_info, collection, false)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
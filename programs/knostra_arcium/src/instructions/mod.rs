@@ -5,6 +5,15 @@ pub mod claim_fees;
 pub mod create;
 pub mod resolve;
 pub mod create_deck;
+pub mod reconcile;
+pub mod forfeit;
+pub mod rollback;
+pub mod update_deck;
+pub mod close_deck;
+pub mod release_deck;
+pub mod freeze;
+pub mod ante;
+pub mod asset_standard;
 
 pub use bet::*;
 pub use cancel::*;
@@ -13,3 +22,12 @@ pub use claim_fees::*;
 pub use create::*;
 pub use resolve::*;
 pub use create_deck::*;
+pub use reconcile::*;
+pub use forfeit::*;
+pub use rollback::*;
+pub use update_deck::*;
+pub use close_deck::*;
+pub use release_deck::*;
+pub use freeze::*;
+pub use ante::*;
+pub use asset_standard::*;
@@ -0,0 +1,153 @@
+use crate::errors::CustomError;
+use crate::events::{MarketUnfrozen, TreasuryDiscrepancy};
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ReconcileTreasury<'info> {
+    #[account(
+        mut,
+        seeds = [b"market", market_account.owner.as_ref(), &market_account.market_id.to_le_bytes()],
+        bump = market_account.bump,
+    )]
+    pub market_account: Account<'info, MarketAccount>,
+
+    #[account(
+        mut,
+        has_one = market_account,
+        seeds = [b"treasury", market_account.key().as_ref()],
+        bump = treasury_account.bump,
+    )]
+    pub treasury_account: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"treasury_vault", market_account.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault: SystemAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Lamports the vault must hold for every booked stake and fee to be paid.
+fn booked_lamports(treasury_account: &TreasuryAccount) -> Result<u64> {
+    let booked = treasury_account
+        .total_amount
+        .checked_add(treasury_account.fee_amount)
+        .and_then(|amount| amount.checked_add(treasury_account.creator_fee_amount))
+        .ok_or(CustomError::MathOverflow)?;
+    Ok(booked)
+}
+
+impl<'info> ReconcileTreasury<'info> {
+    /// Compares the treasury counters against the vault balance. Permissionless,
+    /// so it can be run as a crank; a vault holding less than what is booked is
+    /// frozen when `freeze` is set by the market owner, who alone can `unfreeze`
+    /// it. A surplus is reported but never freezes.
+    fn reconcile(&mut self, freeze: bool) -> Result<()> {
+        let market_account = &mut self.market_account;
+        let treasury_account = &mut self.treasury_account;
+
+        if freeze {
+            require_keys_eq!(
+                self.authority.key(),
+                market_account.owner,
+                CustomError::NotMarketOwner
+            );
+        }
+        let expected = booked_lamports(treasury_account)?;
+        let actual = self.treasury_vault.lamports();
+
+        if actual == expected {
+            return Ok(());
+        }
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        let frozen = freeze && actual < expected && market_account.status != Status::Frozen;
+        if frozen {
+            treasury_account.frozen_from = market_account.status;
+            market_account.status = Status::Frozen;
+            market_account.updated_at = timestamp;
+            treasury_account.status = Status::Frozen;
+        }
+
+        emit!(TreasuryDiscrepancy {
+            market: market_account.key(),
+            expected_lamports: expected,
+            vault_lamports: actual,
+            frozen,
+            status: market_account.status,
+            timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Lifts a freeze once the vault covers what is booked again, e.g. after the
+/// owner topped it up, restoring the status the market was frozen in.
+#[derive(Accounts)]
+pub struct UnfreezeTreasury<'info> {
+    #[account(
+        mut,
+        has_one = owner @ CustomError::NotMarketOwner,
+        seeds = [b"market", market_account.owner.as_ref(), &market_account.market_id.to_le_bytes()],
+        bump = market_account.bump,
+    )]
+    pub market_account: Account<'info, MarketAccount>,
+
+    #[account(
+        mut,
+        has_one = market_account,
+        seeds = [b"treasury", market_account.key().as_ref()],
+        bump = treasury_account.bump,
+    )]
+    pub treasury_account: Account<'info, TreasuryAccount>,
+
+    #[account(
+        seeds = [b"treasury_vault", market_account.key().as_ref()],
+        bump,
+    )]
+    pub treasury_vault: SystemAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+impl<'info> UnfreezeTreasury<'info> {
+    fn unfreeze(&mut self) -> Result<()> {
+        let market_account = &mut self.market_account;
+        let treasury_account = &mut self.treasury_account;
+
+        require!(
+            market_account.status == Status::Frozen,
+            CustomError::InvalidMarketStatus
+        );
+        let actual = self.treasury_vault.lamports();
+        require!(
+            actual >= booked_lamports(treasury_account)?,
+            CustomError::TreasuryShortfall
+        );
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        market_account.status = treasury_account.frozen_from;
+        market_account.updated_at = timestamp;
+        treasury_account.status = treasury_account.frozen_from;
+
+        emit!(MarketUnfrozen {
+            market: market_account.key(),
+            vault_lamports: actual,
+            status: market_account.status,
+            timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handle_reconcile_treasury(ctx: Context<ReconcileTreasury>, freeze: bool) -> Result<()> {
+    ctx.accounts.reconcile(freeze)
+}
+
+pub fn handle_unfreeze_treasury(ctx: Context<UnfreezeTreasury>) -> Result<()> {
+    ctx.accounts.unfreeze()
+}
@@ -0,0 +1,76 @@
+use crate::errors::CustomError;
+use crate::events::DeckReleased;
+use crate::instructions::{CardLock, FREEZE_AUTHORITY_SEED};
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Permissionless: a deck is released once its game has a result, or once the
+/// deck no longer sits in the game because its join was refused or rolled back.
+/// Releasing thaws the cards of the pool seated in the game, which come as
+/// remaining accounts as `deck_assets` reads them.
+#[derive(Accounts)]
+pub struct ReleaseDeck<'info> {
+    #[account(mut)]
+    pub deck_account: Account<'info, DeckAccount>,
+
+    #[account(address = deck_account.locked_game)]
+    pub game_account: Account<'info, GameAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: PDA the deck's cards are frozen under, checked by seeds
+    #[account(seeds = [FREEZE_AUTHORITY_SEED], bump)]
+    pub freeze_authority: UncheckedAccount<'info>,
+
+    /// CHECK: mpl-core program, checked by address
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReleaseDeck<'info> {
+    fn release(
+        &mut self,
+        freeze_authority_bump: u8,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let deck_key = self.deck_account.key();
+        let game = &self.game_account;
+        let pool = self.deck_account.select(&self.deck_account.locked_pool)?;
+
+        let seated = game.player_yes_deck == deck_key || game.player_no_deck == deck_key;
+        require!(
+            game.result != ROUND_PENDING || !seated,
+            CustomError::DeckLocked
+        );
+
+        CardLock {
+            mpl_core_program: &self.mpl_core_program.to_account_info(),
+            freeze_authority: &self.freeze_authority.to_account_info(),
+            freeze_authority_bump,
+            payer: &self.payer.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+        }
+        .thaw_deck(&pool, remaining_accounts)?;
+
+        self.deck_account.locked_game = Pubkey::default();
+        self.deck_account.locked_pool.clear();
+
+        emit!(DeckReleased {
+            deck: deck_key,
+            game: game.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handle_release_deck<'info>(
+    ctx: Context<'_, '_, '_, 'info, ReleaseDeck<'info>>,
+) -> Result<()> {
+    ctx.accounts
+        .release(ctx.bumps.freeze_authority, ctx.remaining_accounts)
+}
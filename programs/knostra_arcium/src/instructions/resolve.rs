@@ -1,4 +1,5 @@
 use crate::errors::CustomError;
+use crate::events::MarketResolved;
 use crate::state::*;
 use anchor_lang::prelude::*;
 // use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
@@ -68,6 +69,10 @@ impl<'info> ResolveMarket<'info> {
             market_account.status == Status::Ongoing,
             CustomError::InvalidMarketStatus
         );
+        require!(
+            market_account.resolution_mode == ResolutionMode::Value,
+            CustomError::ResolutionModeMismatch
+        );
 
         let outcome_yes = match market_account.relational_value.as_str() {
             ">=" => resolve_value >= market_account.target_value,
@@ -88,6 +93,14 @@ impl<'info> ResolveMarket<'info> {
         market_account.updated_at = Clock::get()?.unix_timestamp;
         treasury_account.status = market_account.status;
 
+        emit!(MarketResolved {
+            market: market_account.key(),
+            owner: market_account.owner,
+            resolve_value,
+            status: market_account.status,
+            timestamp: market_account.updated_at,
+        });
+
         Ok(())
     }
 }
@@ -95,3 +108,40 @@ impl<'info> ResolveMarket<'info> {
 pub fn handle_resolve_market(ctx: Context<ResolveMarket>, resolve_value: u64) -> Result<()> {
     ctx.accounts.resolve_market(resolve_value, ctx.program_id)
 }
+
+/// Settles a game-mode market from its game's final result. A drawn game cancels
+/// the market so every bettor is refunded through `claim`, as does a game that
+/// ends before betting has closed. A market its owner already cancelled stays
+/// as it is; any other market can't take a game result.
+pub fn settle_from_game(
+    market_account: &mut Account<MarketAccount>,
+    treasury_account: &mut Account<TreasuryAccount>,
+    result: u8,
+) -> Result<()> {
+    require!(
+        market_account.resolution_mode == ResolutionMode::Game,
+        CustomError::ResolutionModeMismatch
+    );
+
+    market_account.status = match (market_account.status, result) {
+        (Status::Cancelled, _) => return Ok(()),
+        (Status::NotStarted, _) => Status::Cancelled,
+        (Status::Ongoing, ROUND_YES) => Status::ResolvedYes,
+        (Status::Ongoing, ROUND_NO) => Status::ResolvedNo,
+        (Status::Ongoing, _) => Status::Cancelled,
+        _ => return Err(CustomError::InvalidMarketStatus.into()),
+    };
+    market_account.resolve_value = result as u64;
+    market_account.updated_at = Clock::get()?.unix_timestamp;
+    treasury_account.status = market_account.status;
+
+    emit!(MarketResolved {
+        market: market_account.key(),
+        owner: market_account.owner,
+        resolve_value: market_account.resolve_value,
+        status: market_account.status,
+        timestamp: market_account.updated_at,
+    });
+
+    Ok(())
+}
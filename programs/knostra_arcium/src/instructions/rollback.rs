@@ -0,0 +1,48 @@
+use crate::errors::CustomError;
+use crate::events::ComputationFailed;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct RollbackComputation<'info> {
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+}
+
+impl<'info> RollbackComputation<'info> {
+    /// Gives up on a computation whose callback never arrived. Permissionless, so
+    /// a stuck join or turn can be cleared by anyone once the timeout has passed.
+    fn rollback_computation(&mut self) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        require!(
+            self.game_account.computation_timed_out(slot),
+            CustomError::ComputationNotRecoverable
+        );
+
+        fail_computation(&mut self.game_account, true)
+    }
+}
+
+/// Marks the game's computation as failed and reverts what queueing it changed.
+/// Used by the callbacks on an aborted computation and by the timeout rollback.
+pub fn fail_computation(game_account: &mut Account<GameAccount>, timed_out: bool) -> Result<()> {
+    game_account.fail_computation();
+
+    emit!(ComputationFailed {
+        game: game_account.key(),
+        kind: game_account.computation.kind,
+        offset: game_account.computation.offset,
+        timed_out,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handle_rollback_computation(ctx: Context<RollbackComputation>) -> Result<()> {
+    ctx.accounts.rollback_computation()
+}
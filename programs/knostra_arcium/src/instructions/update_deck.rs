@@ -0,0 +1,204 @@
+use crate::errors::CustomError;
+use crate::events::DeckUpdated;
+use crate::instructions::{check_standards, read_held_card};
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateDeck<'info> {
+    #[account(
+        mut,
+        has_one = owner @ CustomError::InvalidDeckOwner,
+    )]
+    pub deck_account: Account<'info, DeckAccount>,
+
+    pub owner: Signer<'info>,
+}
+
+/// Grows the deck account to fit the new cards, the owner paying the rent.
+#[derive(Accounts)]
+#[instruction(mints: Vec<Pubkey>)]
+pub struct AddDeckCards<'info> {
+    #[account(
+        mut,
+        has_one = owner @ CustomError::InvalidDeckOwner,
+        realloc = DeckAccount::space(deck_account.nfts.len() + mints.len()),
+        realloc::payer = owner,
+        realloc::zero = false,
+    )]
+    pub deck_account: Account<'info, DeckAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Shrinks the deck account by the removed card, down to its first page,
+/// refunding the rent to the owner.
+#[derive(Accounts)]
+pub struct RemoveDeckCard<'info> {
+    #[account(
+        mut,
+        has_one = owner @ CustomError::InvalidDeckOwner,
+        realloc = DeckAccount::space(deck_account.nfts.len().saturating_sub(1)),
+        realloc::payer = owner,
+        realloc::zero = false,
+    )]
+    pub deck_account: Account<'info, DeckAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddDeckCards<'info> {
+    /// Appends `mints` to the deck, past its first page if need be. Each
+    /// card's accounts are the remaining accounts, in the same order, as for
+    /// `create_deck`.
+    pub fn add_cards(
+        &mut self,
+        mints: Vec<Pubkey>,
+        standards: Vec<AssetStandard>,
+        proofs: Vec<CompressedProof>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let owner = self.owner.key();
+        let deck_account = &mut self.deck_account;
+        require!(!deck_account.is_locked(), CustomError::DeckLocked);
+        require!(
+            deck_account.nfts.len() + mints.len() <= MAX_DECK_CARDS,
+            CustomError::DeckFull
+        );
+        check_standards(&mints, &standards, &proofs)?;
+
+        let mut accounts = remaining_accounts;
+        let mut proofs = proofs.iter();
+        for (mint, standard) in mints.iter().zip(standards) {
+            let (stats, collection) =
+                read_held_card(&mut accounts, &mut proofs, mint, standard, &owner)?;
+            deck_account.push_card(*mint, standard, stats, collection)?;
+        }
+
+        emit!(DeckUpdated {
+            deck: deck_account.key(),
+            owner,
+            card_count: deck_account.nfts.len() as u64,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RemoveDeckCard<'info> {
+    /// Removes the card at `index`; later cards move down one slot.
+    pub fn remove_card(&mut self, index: u16) -> Result<()> {
+        let deck_account = &mut self.deck_account;
+        require!(!deck_account.is_locked(), CustomError::DeckLocked);
+        require!(
+            (index as usize) < deck_account.nfts.len(),
+            CustomError::InvalidCardIndex
+        );
+        require!(
+            deck_account.nfts.len() > DeckRules::DEFAULT.min_size as usize,
+            CustomError::InvalidDeckSize
+        );
+
+        deck_account.nfts.remove(index as usize);
+        deck_account.standards.remove(index as usize);
+        deck_account.stats.remove(index as usize);
+
+        emit!(DeckUpdated {
+            deck: deck_account.key(),
+            owner: self.owner.key(),
+            card_count: deck_account.nfts.len() as u64,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> UpdateDeck<'info> {
+    /// Puts `mint` in place of the card at `index`. The card's accounts are
+    /// the remaining accounts, as for `create_deck`; `proof` is required for
+    /// a compressed card.
+    pub fn replace_card(
+        &mut self,
+        index: u16,
+        mint: Pubkey,
+        standard: AssetStandard,
+        proof: Option<CompressedProof>,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let owner = self.owner.key();
+        let deck_account = &mut self.deck_account;
+        require!(!deck_account.is_locked(), CustomError::DeckLocked);
+        require!(
+            (index as usize) < deck_account.nfts.len(),
+            CustomError::InvalidCardIndex
+        );
+
+        let mut accounts = remaining_accounts;
+        let (stats, collection) =
+            read_held_card(&mut accounts, &mut proof.iter(), &mint, standard, &owner)?;
+
+        require!(
+            deck_account
+                .nfts
+                .iter()
+                .enumerate()
+                .all(|(i, nft)| i == index as usize || *nft != mint),
+            CustomError::DuplicateCard
+        );
+
+        deck_account.nfts[index as usize] = mint;
+        deck_account.standards[index as usize] = standard;
+        deck_account.stats[index as usize] = stats;
+        // The replaced card may have been the odd one out, but without the
+        // other assets the deck can only stay conservative
+        if deck_account.collection != collection {
+            deck_account.collection = Pubkey::default();
+        }
+
+        self.emit_updated()
+    }
+
+    fn emit_updated(&self) -> Result<()> {
+        emit!(DeckUpdated {
+            deck: self.deck_account.key(),
+            owner: self.owner.key(),
+            card_count: self.deck_account.nfts.len() as u64,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handle_add_deck_cards<'info>(
+    ctx: Context<'_, '_, '_, 'info, AddDeckCards<'info>>,
+    mints: Vec<Pubkey>,
+    standards: Vec<AssetStandard>,
+    proofs: Vec<CompressedProof>,
+) -> Result<()> {
+    ctx.accounts
+        .add_cards(mints, standards, proofs, ctx.remaining_accounts)
+}
+
+pub fn handle_remove_deck_card(ctx: Context<RemoveDeckCard>, index: u16) -> Result<()> {
+    ctx.accounts.remove_card(index)
+}
+
+pub fn handle_replace_deck_card<'info>(
+    ctx: Context<'_, '_, '_, 'info, UpdateDeck<'info>>,
+    index: u16,
+    mint: Pubkey,
+    standard: AssetStandard,
+    proof: Option<CompressedProof>,
+) -> Result<()> {
+    ctx.accounts
+        .replace_card(index, mint, standard, proof, ctx.remaining_accounts)
+}
@@ -6,14 +6,19 @@ use arcium_client::idl::arcium::types::CallbackAccount;
 const COMP_DEF_OFFSET_ADD_TOGETHER: u32 = comp_def_offset("add_together");
 const COMP_DEF_OFFSET_INIT_GAME: u32 = comp_def_offset("init_game");
 const COMP_DEF_OFFSET_JOIN_GAME: u32 = comp_def_offset("join_game");
+const COMP_DEF_OFFSET_DEAL_HAND: u32 = comp_def_offset("deal_hand");
+const COMP_DEF_OFFSET_PLAY_TURN: u32 = comp_def_offset("play_turn");
+const COMP_DEF_OFFSET_REVEAL_GAME: u32 = comp_def_offset("reveal_game");
 
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 
 pub use instructions::*;
 pub use state::*;
 use crate::errors::CustomError;
+use crate::events::{GameJoined, GameRevealed, PlayerViewUpdated, TurnPlayed};
 declare_id!("8KmHKtMP2hsBjk1NEySV3ukWAaUCoxRV22iHcG1YmCWv");
 
 #[arcium_program]
@@ -35,6 +40,21 @@ pub mod knostra_arcium {
         Ok(())
     }
 
+    pub fn init_deal_hand_comp_def(ctx: Context<InitDealHandCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_play_turn_comp_def(ctx: Context<InitPlayTurnCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_game_comp_def(ctx: Context<InitRevealGameCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
     pub fn add_together(
         ctx: Context<AddTogether>,
         computation_offset: u64,
@@ -43,6 +63,11 @@ pub mod knostra_arcium {
         pub_key: [u8; 32],
         nonce: u128,
     ) -> Result<()> {
+        require!(
+            computation_offset
+                == GameAccount::add_together_offset(&ctx.accounts.payer.key(), nonce),
+            CustomError::InvalidComputationOffset
+        );
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         let args = vec![
             Argument::ArcisPubkey(pub_key),
@@ -82,37 +107,74 @@ pub mod knostra_arcium {
     pub fn init_game(
         ctx: Context<InitGame>,
         computation_offset: u64,
-        id: u64,
         nonce: u128,
+        deal_mode: DealMode,
+        ante: bool,
     ) -> Result<()> {
+        let market_key = ctx.accounts.market_account.key();
+        let market_id = ctx.accounts.market_account.market_id;
+        let game_key = ctx.accounts.game_account.key();
+        let clock = Clock::get()?;
         let game = &mut ctx.accounts.game_account;
 
-        game.market_account = game.market_account.key();
-        game.game_id = id;
+        // Either a new game, or a retry of an init computation that failed
+        if game.market_account != Pubkey::default() {
+            require!(
+                game.computation.kind == ComputationKind::InitGame
+                    && game.computation.status == ComputationStatus::Failed,
+                CustomError::ComputationNotRecoverable
+            );
+        }
+
+        game.market_account = market_key;
+        game.game_id = market_id;
         game.nonce = nonce;
+        game.deal_mode = deal_mode;
+        game.ante = ante;
 
         game.player_yes = Pubkey::default();
         game.player_no = Pubkey::default();
         game.player_yes_deck = Pubkey::default();
         game.player_no_deck = Pubkey::default();
+        game.yes_deck_pool.clear();
+        game.no_deck_pool.clear();
 
         // Start game state defaults
         game.current_turn = 0;
-        game.result = 0;
+        game.result = ROUND_PENDING;
+        game.yes_played = false;
+        game.no_played = false;
+        game.yes_wins = 0;
+        game.no_wins = 0;
+        game.deadline = clock.unix_timestamp + GameAccount::JOIN_TIMEOUT;
+        require!(
+            computation_offset == game.next_offset(&game_key, ComputationKind::InitGame, 0),
+            CustomError::InvalidComputationOffset
+        );
+        game.queue_computation(ComputationKind::InitGame, computation_offset, 0, clock.slot);
 
         game.bump = ctx.bumps.game_account;
 
-        let args = vec![Argument::PlaintextU128(nonce)];
+        let args = vec![
+            Argument::PlaintextU128(nonce),
+            Argument::PlaintextU64(computation_offset),
+        ];
 
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![InitGameCallback::callback_ix(&[CallbackAccount {
-                pubkey: ctx.accounts.game_account.key(),
-                is_writable: true,
-            }])],
+            vec![InitGameCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: market_key,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: game_key,
+                    is_writable: true,
+                },
+            ])],
         )?;
 
         Ok(())
@@ -123,79 +185,118 @@ pub mod knostra_arcium {
         ctx: Context<InitGameCallback>,
         output: ComputationOutputs<InitGameOutput>,
     ) -> Result<()> {
-        let o = match output {
-            ComputationOutputs::Success(InitGameOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
-        };
-
-        let nonce = o.nonce;
-
-        let card_ciphertexts: [[u8; 32]; 6] = o.ciphertexts;
-
         let game = &mut ctx.accounts.game_account;
+        if !game.awaits(ComputationKind::InitGame) {
+            return Ok(());
+        }
 
-        game.yes_cards1 = card_ciphertexts[0];
-        game.yes_cards2 = card_ciphertexts[1];
-        game.yes_cards3 = card_ciphertexts[2];
-        game.no_cards1 = card_ciphertexts[3];
-        game.no_cards2 = card_ciphertexts[4];
-        game.no_cards3 = card_ciphertexts[5];
+        let (state, computation_offset) = match output {
+            ComputationOutputs::Success(InitGameOutput {
+                field_0:
+                    InitGameOutputStruct0 {
+                        field_0: state,
+                        field_1: computation_offset,
+                    },
+            }) => (state, computation_offset),
+            _ => return instructions::fail_computation(game, false),
+        };
+        game.verify_callback(computation_offset)?;
 
-        game.nonce = nonce;
+        game.set_encrypted_state(state.ciphertexts, state.nonce);
+        game.computation.status = ComputationStatus::Finalized;
 
         Ok(())
     }
 
-    pub fn join_game(
-        ctx: Context<JoinGame>,
+    /// The player sits down with the cards of their deck at the indices in
+    /// `deck_pool` (see `DeckAccount::select`), which may come from anywhere in
+    /// the deck; card indices, `ante_card` included, are indices into the pool.
+    /// `ante_card` is the card escrowed in an ante game. Only mpl-core cards
+    /// play (see `check_deck_owned`).
+    pub fn join_game<'info>(
+        ctx: Context<'_, '_, '_, 'info, JoinGame<'info>>,
         computation_offset: u64,
         player_cards1: [u8; 32],
         player_cards2: [u8; 32],
         player_cards3: [u8; 32],
+        pub_key: [u8; 32],
         nonce: u128,
+        ante_card: Option<u8>,
+        deck_pool: Vec<u16>,
     ) -> Result<()> {
         let payer_key = ctx.accounts.payer.key();
+        let market_key = ctx.accounts.market_account.key();
         let game_key = ctx.accounts.game_account.key();
         let game_nonce = ctx.accounts.game_account.nonce;
-
-        let bet_account = &mut ctx.accounts.bet_account;
-        let deck_account = &mut ctx.accounts.deck_account;
-        let game = &mut ctx.accounts.game_account;
-
-        require_keys_eq!(bet_account.user, payer_key, CustomError::InvalidPayer);
-
-        // Determine player side: 0 = yes, 1 = no
-        let player_side = if bet_account.choice { 0 } else { 1 };
-
-        // Assign the player and deck if not already joined
-        if player_side == 0 {
-            if game.player_yes == Pubkey::default() {
-                game.player_yes = payer_key;
-                game.player_yes_deck = deck_account.key();
-            } else {
-                return Err(CustomError::PlayerAlreadyJoined.into());
-            }
-        } else {
-            if game.player_no == Pubkey::default() {
-                game.player_no = payer_key;
-                game.player_no_deck = deck_account.key();
-            } else {
-                return Err(CustomError::PlayerAlreadyJoined.into());
-            }
+        let pool = ctx.accounts.deck_account.select(&deck_pool)?;
+
+        require!(
+            ctx.accounts.game_account.deal_mode == DealMode::Chosen,
+            CustomError::InvalidDealMode
+        );
+        // The pool's card accounts follow as remaining accounts
+        instructions::check_deck_owned(&pool, &payer_key, ctx.remaining_accounts)?;
+        require!(
+            ante_card.is_some() == ctx.accounts.game_account.ante,
+            CustomError::InvalidAnte
+        );
+        let mpl_core_program = ctx.accounts.mpl_core_program.to_account_info();
+        let freeze_authority = ctx.accounts.freeze_authority.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let card_lock = CardLock {
+            mpl_core_program: &mpl_core_program,
+            freeze_authority: &freeze_authority,
+            freeze_authority_bump: ctx.bumps.freeze_authority,
+            payer: &payer,
+            system_program: &system_program,
+        };
+        let player_side = seat_player(
+            &game_key,
+            &mut ctx.accounts.game_account,
+            &ctx.accounts.bet_account,
+            &ctx.accounts.market_account.deck_rules,
+            &mut ctx.accounts.deck_account,
+            &pool,
+            deck_pool,
+            payer_key,
+            pub_key,
+            computation_offset,
+        )?;
+        card_lock.freeze_deck(&pool, ante_card, ctx.remaining_accounts)?;
+        if let Some(index) = ante_card {
+            card_lock.escrow_ante(
+                &mut ctx.accounts.game_account,
+                &pool,
+                player_side,
+                index,
+                ctx.remaining_accounts,
+            )?;
         }
 
-        // Prepare Arcium encrypted computation args
-        let args = vec![
-            Argument::ArcisPubkey(payer_key.to_bytes()),
+        // Prepare Arcium encrypted computation args, in circuit parameter order
+        let mut args = vec![
+            Argument::ArcisPubkey(pub_key),
             Argument::PlaintextU128(nonce),
-            Argument::PlaintextU8(player_side),
             Argument::EncryptedU8(player_cards1),
             Argument::EncryptedU8(player_cards2),
             Argument::EncryptedU8(player_cards3),
             Argument::PlaintextU128(game_nonce),
-            Argument::Account(game_key, 8, 32 * 6),
+            Argument::Account(
+                game_key,
+                GameAccount::ENCRYPTED_STATE_OFFSET,
+                GameAccount::ENCRYPTED_STATE_LEN,
+            ),
+            Argument::PlaintextU8(player_side),
         ];
 
+        // Stats table of the pool the circuit looks the selected cards up in
+        let (powers, elements) = pool.padded_stats();
+        args.extend(powers.into_iter().map(Argument::PlaintextU8));
+        args.extend(elements.into_iter().map(Argument::PlaintextU8));
+        args.push(Argument::PlaintextU8(pool.nfts.len() as u8));
+        args.push(Argument::PlaintextU64(computation_offset));
+
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
         queue_computation(
@@ -203,10 +304,16 @@ pub mod knostra_arcium {
             computation_offset,
             args,
             None,
-            vec![JoinGameCallback::callback_ix(&[CallbackAccount {
-                pubkey: game_key,
-                is_writable: true,
-            }])],
+            vec![JoinGameCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: market_key,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: game_key,
+                    is_writable: true,
+                },
+            ])],
         )?;
 
         Ok(())
@@ -218,25 +325,455 @@ pub mod knostra_arcium {
         ctx: Context<JoinGameCallback>,
         output: ComputationOutputs<JoinGameOutput>,
     ) -> Result<()> {
-        let o = match output {
-            ComputationOutputs::Success(JoinGameOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
+        let game_key = ctx.accounts.game_account.key();
+        let game = &mut ctx.accounts.game_account;
+        if !game.awaits(ComputationKind::JoinGame) {
+            return Ok(());
+        }
+
+        let (state, accepted, player_side, view, computation_offset) = match output {
+            ComputationOutputs::Success(JoinGameOutput {
+                field_0:
+                    JoinGameOutputStruct0 {
+                        field_0: state,
+                        field_1: accepted,
+                        field_2: player_side,
+                        field_3: view,
+                        field_4: computation_offset,
+                    },
+            }) => (state, accepted, player_side, view, computation_offset),
+            _ => return instructions::fail_computation(game, false),
+        };
+        game.verify_callback(computation_offset)?;
+
+        game.set_encrypted_state(state.ciphertexts, state.nonce);
+        game.computation.status = ComputationStatus::Finalized;
+
+        finish_join(game_key, game, accepted, player_side, view.ciphertexts, view.nonce)
+    }
+
+    /// Like `join_game`, but the MXE deals the hand from a shuffle of the deck pool,
+    /// which must hold the whole deck or a full page of it (see
+    /// `DeckAccount::check_deal_pool`).
+    /// `pub_key` is the x25519 key the player's views are encrypted to.
+    pub fn deal_hand<'info>(
+        ctx: Context<'_, '_, '_, 'info, DealHand<'info>>,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+        ante_card: Option<u8>,
+        deck_pool: Vec<u16>,
+    ) -> Result<()> {
+        let payer_key = ctx.accounts.payer.key();
+        let market_key = ctx.accounts.market_account.key();
+        let game_key = ctx.accounts.game_account.key();
+        let game_nonce = ctx.accounts.game_account.nonce;
+        let pool = ctx.accounts.deck_account.select(&deck_pool)?;
+
+        require!(
+            ctx.accounts.game_account.deal_mode == DealMode::Shuffled,
+            CustomError::InvalidDealMode
+        );
+        ctx.accounts.deck_account.check_deal_pool(&deck_pool)?;
+        // The pool's card accounts follow as remaining accounts
+        instructions::check_deck_owned(&pool, &payer_key, ctx.remaining_accounts)?;
+        require!(
+            ante_card.is_some() == ctx.accounts.game_account.ante,
+            CustomError::InvalidAnte
+        );
+        let mpl_core_program = ctx.accounts.mpl_core_program.to_account_info();
+        let freeze_authority = ctx.accounts.freeze_authority.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let card_lock = CardLock {
+            mpl_core_program: &mpl_core_program,
+            freeze_authority: &freeze_authority,
+            freeze_authority_bump: ctx.bumps.freeze_authority,
+            payer: &payer,
+            system_program: &system_program,
         };
+        let player_side = seat_player(
+            &game_key,
+            &mut ctx.accounts.game_account,
+            &ctx.accounts.bet_account,
+            &ctx.accounts.market_account.deck_rules,
+            &mut ctx.accounts.deck_account,
+            &pool,
+            deck_pool,
+            payer_key,
+            pub_key,
+            computation_offset,
+        )?;
+        card_lock.freeze_deck(&pool, ante_card, ctx.remaining_accounts)?;
+        if let Some(index) = ante_card {
+            card_lock.escrow_ante(
+                &mut ctx.accounts.game_account,
+                &pool,
+                player_side,
+                index,
+                ctx.remaining_accounts,
+            )?;
+        }
+
+        let mut args = vec![
+            Argument::ArcisPubkey(pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::PlaintextU128(game_nonce),
+            Argument::Account(
+                game_key,
+                GameAccount::ENCRYPTED_STATE_OFFSET,
+                GameAccount::ENCRYPTED_STATE_LEN,
+            ),
+            Argument::PlaintextU8(player_side),
+        ];
 
-        let nonce = o.nonce;
+        let (powers, elements) = pool.padded_stats();
+        args.extend(powers.into_iter().map(Argument::PlaintextU8));
+        args.extend(elements.into_iter().map(Argument::PlaintextU8));
+        args.push(Argument::PlaintextU8(pool.nfts.len() as u8));
+        args.push(Argument::PlaintextU64(computation_offset));
 
-        let card_ciphertexts: [[u8; 32]; 6] = o.ciphertexts;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
 
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![DealHandCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: market_key,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: game_key,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "deal_hand")]
+    pub fn deal_hand_callback(
+        ctx: Context<DealHandCallback>,
+        output: ComputationOutputs<DealHandOutput>,
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game_account.key();
         let game = &mut ctx.accounts.game_account;
+        // Dealing is the join of a shuffled game and is tracked as one
+        if !game.awaits(ComputationKind::JoinGame) {
+            return Ok(());
+        }
 
-        game.yes_cards1 = card_ciphertexts[0];
-        game.yes_cards2 = card_ciphertexts[1];
-        game.yes_cards3 = card_ciphertexts[2];
-        game.no_cards1 = card_ciphertexts[3];
-        game.no_cards2 = card_ciphertexts[4];
-        game.no_cards3 = card_ciphertexts[5];
+        let (state, accepted, player_side, view, computation_offset) = match output {
+            ComputationOutputs::Success(DealHandOutput {
+                field_0:
+                    DealHandOutputStruct0 {
+                        field_0: state,
+                        field_1: accepted,
+                        field_2: player_side,
+                        field_3: view,
+                        field_4: computation_offset,
+                    },
+            }) => (state, accepted, player_side, view, computation_offset),
+            _ => return instructions::fail_computation(game, false),
+        };
+        game.verify_callback(computation_offset)?;
 
-        game.nonce = nonce;
+        game.set_encrypted_state(state.ciphertexts, state.nonce);
+        game.computation.status = ComputationStatus::Finalized;
+
+        finish_join(game_key, game, accepted, player_side, view.ciphertexts, view.nonce)
+    }
+
+    pub fn play_turn(
+        ctx: Context<PlayTurn>,
+        computation_offset: u64,
+        card_choice: [u8; 32],
+        pub_key: [u8; 32],
+        nonce: u128,
+    ) -> Result<()> {
+        let payer_key = ctx.accounts.payer.key();
+        let game_key = ctx.accounts.game_account.key();
+        let market_key = ctx.accounts.market_account.key();
+        let treasury_key = ctx.accounts.treasury_account.key();
+        let market = &ctx.accounts.market_account;
+        let game = &mut ctx.accounts.game_account;
+
+        require!(game.result == ROUND_PENDING, CustomError::GameFinished);
+        // A game that settles its market is only played once betting has closed
+        require!(
+            market.resolution_mode != ResolutionMode::Game || market.status == Status::Ongoing,
+            CustomError::InvalidMarketStatus
+        );
+        require!(
+            game.player_yes != Pubkey::default() && game.player_no != Pubkey::default(),
+            CustomError::GameNotReady
+        );
+        require!(
+            game.computation.status != ComputationStatus::Pending,
+            CustomError::ComputationPending
+        );
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp <= game.deadline, CustomError::GameExpired);
+
+        // Determine player side: 0 = yes, 1 = no
+        let player_side = if payer_key == game.player_yes {
+            require!(!game.yes_played, CustomError::TurnAlreadyPlayed);
+            game.yes_played = true;
+            0
+        } else if payer_key == game.player_no {
+            require!(!game.no_played, CustomError::TurnAlreadyPlayed);
+            game.no_played = true;
+            1
+        } else {
+            return Err(CustomError::InvalidPayer.into());
+        };
+        require!(
+            computation_offset == game.next_offset(&game_key, ComputationKind::PlayTurn, player_side),
+            CustomError::InvalidComputationOffset
+        );
+        game.queue_computation(ComputationKind::PlayTurn, computation_offset, player_side, clock.slot);
+
+        let args = vec![
+            Argument::ArcisPubkey(pub_key),
+            Argument::PlaintextU128(nonce),
+            Argument::EncryptedU8(card_choice),
+            Argument::PlaintextU128(game.nonce),
+            Argument::Account(
+                game_key,
+                GameAccount::ENCRYPTED_STATE_OFFSET,
+                GameAccount::ENCRYPTED_STATE_LEN,
+            ),
+            Argument::PlaintextU8(player_side),
+            Argument::PlaintextU8(game.current_turn),
+            // The views are encrypted with the computation offset as nonce, which is
+            // never reused within a game
+            Argument::ArcisPubkey(game.yes_view_key),
+            Argument::PlaintextU128(computation_offset as u128),
+            Argument::ArcisPubkey(game.no_view_key),
+            Argument::PlaintextU128(computation_offset as u128),
+            Argument::PlaintextU64(computation_offset),
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PlayTurnCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: market_key,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: game_key,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: treasury_key,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "play_turn")]
+    pub fn play_turn_callback(
+        ctx: Context<PlayTurnCallback>,
+        output: ComputationOutputs<PlayTurnOutput>,
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game_account.key();
+        let game = &mut ctx.accounts.game_account;
+        if !game.awaits(ComputationKind::PlayTurn) {
+            return Ok(());
+        }
+
+        let (state, round_winner, yes_view, no_view, computation_offset) = match output {
+            ComputationOutputs::Success(PlayTurnOutput {
+                field_0:
+                    PlayTurnOutputStruct0 {
+                        field_0: state,
+                        field_1: round_winner,
+                        field_2: yes_view,
+                        field_3: no_view,
+                        field_4: computation_offset,
+                    },
+            }) => (state, round_winner, yes_view, no_view, computation_offset),
+            _ => return instructions::fail_computation(game, false),
+        };
+        game.verify_callback(computation_offset)?;
+
+        game.set_encrypted_state(state.ciphertexts, state.nonce);
+        game.computation.status = ComputationStatus::Finalized;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        for (side, player, view) in [(0, game.player_yes, yes_view), (1, game.player_no, no_view)] {
+            emit!(PlayerViewUpdated {
+                game: game_key,
+                player,
+                side,
+                view: view.ciphertexts,
+                nonce: view.nonce,
+                timestamp,
+            });
+        }
+
+        if round_winner == ROUND_PENDING {
+            return Ok(());
+        }
+
+        match round_winner {
+            ROUND_YES => game.yes_wins += 1,
+            ROUND_NO => game.no_wins += 1,
+            _ => {}
+        }
+        game.round_winners[game.current_turn as usize] = round_winner;
+        game.yes_played = false;
+        game.no_played = false;
+        game.current_turn += 1;
+        game.deadline = timestamp + GameAccount::TURN_TIMEOUT;
+
+        if game.current_turn >= GameAccount::ROUNDS {
+            game.result = if game.yes_wins > game.no_wins {
+                ROUND_YES
+            } else if game.no_wins > game.yes_wins {
+                ROUND_NO
+            } else {
+                ROUND_DRAW
+            };
+
+            instructions::settle_from_game(
+                &mut ctx.accounts.market_account,
+                &mut ctx.accounts.treasury_account,
+                game.result,
+            )?;
+        }
+
+        emit!(TurnPlayed {
+            game: game_key,
+            market: game.market_account,
+            turn: game.current_turn,
+            round_winner,
+            yes_wins: game.yes_wins,
+            no_wins: game.no_wins,
+            result: game.result,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Queues the reveal of a finished game's hands and rounds into its
+    /// `GameHistory`. Anyone may pay for it once the game has a result.
+    pub fn reveal_game(ctx: Context<RevealGame>, computation_offset: u64) -> Result<()> {
+        let game_key = ctx.accounts.game_account.key();
+        let slot = Clock::get()?.slot;
+        let game = &mut ctx.accounts.game_account;
+        let history = &mut ctx.accounts.game_history;
+
+        require!(game.result != ROUND_PENDING, CustomError::GameNotFinished);
+        require!(!history.revealed, CustomError::GameAlreadyRevealed);
+        require!(
+            game.computation.status != ComputationStatus::Pending,
+            CustomError::ComputationPending
+        );
+        require!(
+            computation_offset == game.next_offset(&game_key, ComputationKind::RevealGame, 0),
+            CustomError::InvalidComputationOffset
+        );
+        game.queue_computation(ComputationKind::RevealGame, computation_offset, 0, slot);
+
+        history.game_account = game_key;
+        history.market_account = game.market_account;
+        history.player_yes = game.player_yes;
+        history.player_yes_deck = game.player_yes_deck;
+        history.player_no = game.player_no;
+        history.player_no_deck = game.player_no_deck;
+        history.yes_deck_pool = game.yes_deck_pool.clone();
+        history.no_deck_pool = game.no_deck_pool.clone();
+        history.round_winners = game.round_winners;
+        history.result = game.result;
+        history.bump = ctx.bumps.game_history;
+
+        let history_key = history.key();
+        let market_key = game.market_account;
+        let args = vec![
+            Argument::PlaintextU128(game.nonce),
+            Argument::Account(
+                game_key,
+                GameAccount::ENCRYPTED_STATE_OFFSET,
+                GameAccount::ENCRYPTED_STATE_LEN,
+            ),
+            Argument::PlaintextU64(computation_offset),
+        ];
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealGameCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: market_key,
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: game_key,
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: history_key,
+                    is_writable: true,
+                },
+            ])],
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_game")]
+    pub fn reveal_game_callback(
+        ctx: Context<RevealGameCallback>,
+        output: ComputationOutputs<RevealGameOutput>,
+    ) -> Result<()> {
+        let game_key = ctx.accounts.game_account.key();
+        let history_key = ctx.accounts.game_history.key();
+        let game = &mut ctx.accounts.game_account;
+        if !game.awaits(ComputationKind::RevealGame) {
+            return Ok(());
+        }
+
+        let (record, computation_offset) = match output {
+            ComputationOutputs::Success(RevealGameOutput {
+                field_0:
+                    RevealGameOutputStruct0 {
+                        field_0: record,
+                        field_1: computation_offset,
+                    },
+            }) => (record, computation_offset),
+            _ => return instructions::fail_computation(game, false),
+        };
+        game.verify_callback(computation_offset)?;
+        game.computation.status = ComputationStatus::Finalized;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        ctx.accounts.game_history.set_revealed(record, timestamp);
+
+        emit!(GameRevealed {
+            game: game_key,
+            history: history_key,
+            result: game.result,
+            timestamp,
+        });
 
         Ok(())
     }
@@ -274,9 +811,66 @@ pub mod knostra_arcium {
         ctx: Context<'_, '_, '_, 'info, CreateDeckAccount<'info>>,
         seed: u64,
         mints: Vec<Pubkey>,
+        standards: Vec<AssetStandard>,
+        proofs: Vec<CompressedProof>,
         bump: u8,
     ) -> Result<()> {
-        instructions::handle_create_deck(ctx, seed, mints, bump)
+        instructions::handle_create_deck(ctx, seed, mints, standards, proofs, bump)
+    }
+
+    pub fn add_deck_cards<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddDeckCards<'info>>,
+        mints: Vec<Pubkey>,
+        standards: Vec<AssetStandard>,
+        proofs: Vec<CompressedProof>,
+    ) -> Result<()> {
+        instructions::handle_add_deck_cards(ctx, mints, standards, proofs)
+    }
+
+    pub fn remove_deck_card(ctx: Context<RemoveDeckCard>, index: u16) -> Result<()> {
+        instructions::handle_remove_deck_card(ctx, index)
+    }
+
+    pub fn replace_deck_card<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateDeck<'info>>,
+        index: u16,
+        mint: Pubkey,
+        standard: AssetStandard,
+        proof: Option<CompressedProof>,
+    ) -> Result<()> {
+        instructions::handle_replace_deck_card(ctx, index, mint, standard, proof)
+    }
+
+    pub fn close_deck(ctx: Context<CloseDeck>) -> Result<()> {
+        instructions::handle_close_deck(ctx)
+    }
+
+    pub fn settle_ante<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleAnte<'info>>,
+    ) -> Result<()> {
+        instructions::handle_settle_ante(ctx)
+    }
+
+    pub fn release_deck<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReleaseDeck<'info>>,
+    ) -> Result<()> {
+        instructions::handle_release_deck(ctx)
+    }
+
+    pub fn reconcile(ctx: Context<ReconcileTreasury>, freeze: bool) -> Result<()> {
+        instructions::handle_reconcile_treasury(ctx, freeze)
+    }
+
+    pub fn unfreeze(ctx: Context<UnfreezeTreasury>) -> Result<()> {
+        instructions::handle_unfreeze_treasury(ctx)
+    }
+
+    pub fn claim_forfeit(ctx: Context<ClaimForfeit>) -> Result<()> {
+        instructions::handle_claim_forfeit(ctx)
+    }
+
+    pub fn rollback_computation(ctx: Context<RollbackComputation>) -> Result<()> {
+        instructions::handle_rollback_computation(ctx)
     }
 }
 
@@ -392,7 +986,7 @@ pub struct InitInitGameCompDef<'info> {
 
 #[queue_computation_accounts("init_game", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64, id: u64)]
+#[instruction(computation_offset: u64)]
 pub struct InitGame<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -447,13 +1041,24 @@ pub struct InitGame<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(init,
+    // Only the market's owner sets up its game, before the market settles
+    #[account(
+        seeds = [b"market", market_account.owner.as_ref(), &market_account.market_id.to_le_bytes()],
+        bump = market_account.bump,
+        constraint = market_account.owner == payer.key() @ CustomError::NotMarketOwner,
+        constraint = market_account.resolution_mode == ResolutionMode::Game
+            @ CustomError::ResolutionModeMismatch,
+        constraint = matches!(market_account.status, Status::NotStarted | Status::Ongoing)
+            @ CustomError::InvalidMarketStatus,
+    )]
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(init_if_needed,
         payer = payer,
         space = 8 + GameAccount::INIT_SPACE,
-        seeds = [b"rps_game", id.to_le_bytes().as_ref()],
+        seeds = [b"game", market_account.key().as_ref()],
         bump,
     )]
-    pub game_account: Account<'info, GameAccount>,
+    pub game_account: Box<Account<'info, GameAccount>>,
 }
 
 #[callback_accounts("init_game")]
@@ -467,8 +1072,15 @@ pub struct InitGameCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
-    #[account(mut)]
-    pub game_account: Account<'info, GameAccount>,
+    // The game is derived from the market queued with it, not from its own
+    // fields, so another game's account can't stand in for it
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        mut,
+        seeds = [b"game", market_account.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
 }
 
 #[init_computation_definition_accounts("join_game", payer)]
@@ -501,8 +1113,15 @@ pub struct JoinGameCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
-    #[account(mut)]
-    pub game_account: Account<'info, GameAccount>,
+    // The game is derived from the market queued with it, not from its own
+    // fields, so another game's account can't stand in for it
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        mut,
+        seeds = [b"game", market_account.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
 }
 
 #[queue_computation_accounts("join_game", payer)]
@@ -562,12 +1181,492 @@ pub struct JoinGame<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(mut)]
+    pub deck_account: Box<Account<'info, DeckAccount>>,
+    pub bet_account: Account<'info, BetAccount>,
+    #[account(address = game_account.market_account)]
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    /// CHECK: PDA the deck's cards are frozen under, checked by seeds
+    #[account(seeds = [FREEZE_AUTHORITY_SEED], bump)]
+    pub freeze_authority: UncheckedAccount<'info>,
+    /// CHECK: mpl-core program, checked by address
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+}
+
+#[init_computation_definition_accounts("play_turn", payer)]
+#[derive(Accounts)]
+pub struct InitPlayTurnCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("play_turn", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlayTurn<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_TURN)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(address = game_account.market_account)]
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        has_one = market_account,
+        seeds = [b"treasury", market_account.key().as_ref()],
+        bump = treasury_account.bump,
+    )]
+    pub treasury_account: Box<Account<'info, TreasuryAccount>>,
+}
+
+#[callback_accounts("play_turn")]
+#[derive(Accounts)]
+pub struct PlayTurnCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLAY_TURN)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    // The game is derived from the market queued with it, not from its own
+    // fields, so another game's account can't stand in for it
     #[account(mut)]
-    pub game_account: Account<'info, GameAccount>,
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        mut,
+        seeds = [b"game", market_account.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(
+        mut,
+        has_one = market_account,
+        seeds = [b"treasury", market_account.key().as_ref()],
+        bump = treasury_account.bump,
+    )]
+    pub treasury_account: Box<Account<'info, TreasuryAccount>>,
+}
+
+#[init_computation_definition_accounts("reveal_game", payer)]
+#[derive(Accounts)]
+pub struct InitRevealGameCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub deck_account: Account<'info, DeckAccount>,
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_game", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealGame<'info> {
     #[account(mut)]
-    pub bet_account: Account<'info, BetAccount>
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_GAME)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + GameHistory::INIT_SPACE,
+        seeds = [b"history", game_account.key().as_ref()],
+        bump,
+    )]
+    pub game_history: Box<Account<'info, GameHistory>>,
+}
+
+
+#[callback_accounts("reveal_game")]
+#[derive(Accounts)]
+pub struct RevealGameCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_GAME)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    // The game is derived from the market queued with it, not from its own
+    // fields, so another game's account can't stand in for it
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        mut,
+        seeds = [b"game", market_account.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(
+        mut,
+        seeds = [b"history", game_account.key().as_ref()],
+        bump = game_history.bump,
+    )]
+    pub game_history: Box<Account<'info, GameHistory>>,
+}
+
+#[init_computation_definition_accounts("deal_hand", payer)]
+#[derive(Accounts)]
+pub struct InitDealHandCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    /// Can't check it here as it's not initialized yet.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("deal_hand", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct DealHand<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account, checked by the arcium program
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool, checked by the arcium program
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEAL_HAND)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS,
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        mut,
+        seeds = [b"game", game_account.market_account.as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+    #[account(mut)]
+    pub deck_account: Box<Account<'info, DeckAccount>>,
+    pub bet_account: Account<'info, BetAccount>,
+    #[account(address = game_account.market_account)]
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    /// CHECK: PDA the deck's cards are frozen under, checked by seeds
+    #[account(seeds = [FREEZE_AUTHORITY_SEED], bump)]
+    pub freeze_authority: UncheckedAccount<'info>,
+    /// CHECK: mpl-core program, checked by address
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+}
+
+#[callback_accounts("deal_hand")]
+#[derive(Accounts)]
+pub struct DealHandCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_DEAL_HAND)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+    // The game is derived from the market queued with it, not from its own
+    // fields, so another game's account can't stand in for it
+    pub market_account: Box<Account<'info, MarketAccount>>,
+    #[account(
+        mut,
+        seeds = [b"game", market_account.key().as_ref()],
+        bump = game_account.bump,
+    )]
+    pub game_account: Box<Account<'info, GameAccount>>,
+}
+
+/// Checks shared by `join_game` and `deal_hand`, then seats the payer on the side
+/// of their bet and records the queued join. Returns the side, 0 = yes, 1 = no.
+#[allow(clippy::too_many_arguments)]
+fn seat_player(
+    game_key: &Pubkey,
+    game: &mut GameAccount,
+    bet_account: &BetAccount,
+    deck_rules: &DeckRules,
+    deck_account: &mut Account<DeckAccount>,
+    pool: &DeckAccount,
+    deck_pool: Vec<u16>,
+    payer_key: Pubkey,
+    pub_key: [u8; 32],
+    computation_offset: u64,
+) -> Result<u8> {
+    game.check_seat(bet_account, deck_account, &payer_key)?;
+    deck_rules.check(pool)?;
+    // A deck refused from this game is released before it joins again, so the
+    // join freezes its whole pool afresh: an ante handed back in between is no
+    // longer frozen
+    require!(!deck_account.is_locked(), CustomError::DeckLocked);
+    require!(game.result == ROUND_PENDING, CustomError::GameFinished);
+    require!(
+        game.computation.status != ComputationStatus::Pending,
+        CustomError::ComputationPending
+    );
+    require!(
+        game.computation.kind != ComputationKind::InitGame
+            || game.computation.status == ComputationStatus::Finalized,
+        CustomError::GameNotReady
+    );
+    let clock = Clock::get()?;
+    require!(clock.unix_timestamp <= game.deadline, CustomError::GameExpired);
+
+    // Determine player side: 0 = yes, 1 = no
+    let player_side = if bet_account.choice { 0 } else { 1 };
+
+    // Assign the player and deck if not already joined
+    if player_side == 0 {
+        require!(game.player_yes == Pubkey::default(), CustomError::PlayerAlreadyJoined);
+        game.player_yes = payer_key;
+        game.player_yes_deck = deck_account.key();
+        game.yes_deck_pool = deck_pool.clone();
+        game.yes_view_key = pub_key;
+    } else {
+        require!(game.player_no == Pubkey::default(), CustomError::PlayerAlreadyJoined);
+        game.player_no = payer_key;
+        game.player_no_deck = deck_account.key();
+        game.no_deck_pool = deck_pool.clone();
+        game.no_view_key = pub_key;
+    }
+    require!(
+        computation_offset == game.next_offset(game_key, ComputationKind::JoinGame, player_side),
+        CustomError::InvalidComputationOffset
+    );
+    game.queue_computation(ComputationKind::JoinGame, computation_offset, player_side, clock.slot);
+    deck_account.locked_game = *game_key;
+    deck_account.locked_pool = deck_pool;
+
+    Ok(player_side)
+}
+
+/// Applies the outcome of a join or deal once its new state is stored.
+fn finish_join(
+    game_key: Pubkey,
+    game: &mut GameAccount,
+    accepted: bool,
+    player_side: u8,
+    view: [[u8; 32]; GameAccount::PLAYER_VIEW_LEN],
+    view_nonce: u128,
+) -> Result<()> {
+    let player = if player_side == 0 {
+        game.player_yes
+    } else {
+        game.player_no
+    };
+
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    // The MXE refused the join, free the side again. The side will join at a
+    // new offset, so it counts as a failure.
+    if !accepted {
+        game.failed_computations += 1;
+        game.leave(player_side);
+    } else if game.player_yes != Pubkey::default() && game.player_no != Pubkey::default() {
+        // Both sides are in, the first turn clock starts now
+        game.deadline = timestamp + GameAccount::TURN_TIMEOUT;
+    }
+
+    emit!(GameJoined {
+        game: game_key,
+        market: game.market_account,
+        player,
+        side: player_side,
+        accepted,
+        timestamp,
+    });
+
+    if accepted {
+        emit!(PlayerViewUpdated {
+            game: game_key,
+            player,
+            side: player_side,
+            view,
+            nonce: view_nonce,
+            timestamp,
+        });
+    }
+
+    Ok(())
 }
 
 #[event]
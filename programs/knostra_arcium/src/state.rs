@@ -1,3 +1,4 @@
+use crate::errors::CustomError;
 use anchor_lang::prelude::*;
 
 #[derive(InitSpace)]
@@ -23,6 +24,8 @@ pub struct MarketAccount {
     pub market_id: u64,
     pub created_at: i64,
     pub updated_at: i64,
+    pub resolution_mode: ResolutionMode,
+    pub deck_rules: DeckRules,
 }
 
 #[derive(InitSpace)]
@@ -37,6 +40,8 @@ pub struct TreasuryAccount {
     pub yes_count: u64,
     pub no_count: u64,
     pub status: Status,
+    // Status the market had when `reconcile` froze it, restored by `unfreeze`
+    pub frozen_from: Status,
 }
 
 #[derive(InitSpace)]
@@ -50,6 +55,18 @@ pub struct BetAccount {
     pub claimed: bool,
 }
 
+// Must match `MAX_DECK_SIZE` in the circuits. A game plays from a pool of at
+// most this many cards of a deck
+pub const MAX_DECK_SIZE: usize = 20;
+
+// Cards a deck can hold once `add_deck_cards` has grown it past its first page
+pub const MAX_DECK_CARDS: usize = 400;
+
+// Bytes each card takes across `nfts`, `stats` and `standards`
+const DECK_CARD_SPACE: usize = 32 + CardStats::INIT_SPACE + AssetStandard::INIT_SPACE;
+
+/// Space is allocated for one page of cards at creation; `add_deck_cards`
+/// reallocates the account for more.
 #[derive(InitSpace)]
 #[account]
 pub struct DeckAccount {
@@ -57,6 +74,215 @@ pub struct DeckAccount {
     #[max_len(20)]
     pub nfts: Vec<Pubkey>,
     pub bump: u8,
+    // Stats of `nfts[i]`, read from the asset's Attributes plugin
+    #[max_len(20)]
+    pub stats: Vec<CardStats>,
+    // Game the deck is seated in; the deck can't change until it is released
+    pub locked_game: Pubkey,
+    // Collection every card belongs to, or default when they don't share one
+    pub collection: Pubkey,
+    // Standard `nfts[i]` is held under
+    #[max_len(20)]
+    pub standards: Vec<AssetStandard>,
+    // Pool seated in `locked_game` (see `select`), the cards frozen for it
+    #[max_len(20)]
+    pub locked_pool: Vec<u16>,
+}
+
+impl DeckAccount {
+    /// Account size for a deck of `cards` cards, never below the first page.
+    pub fn space(cards: usize) -> usize {
+        8 + Self::INIT_SPACE + DECK_CARD_SPACE * cards.saturating_sub(MAX_DECK_SIZE)
+    }
+
+    /// The cards at the deck indices in `pool`, in that order, as a deck of
+    /// their own: the table a game joined with this pool plays from. A pool
+    /// holds up to `MAX_DECK_SIZE` distinct cards from anywhere in the deck.
+    /// Card indices in the game are indices into the pool.
+    pub fn select(&self, pool: &[u16]) -> Result<DeckAccount> {
+        require!(
+            pool.len() <= MAX_DECK_SIZE
+                && pool.iter().all(|&index| (index as usize) < self.nfts.len())
+                && pool
+                    .iter()
+                    .enumerate()
+                    .all(|(i, index)| !pool[..i].contains(index)),
+            CustomError::InvalidDeckPool
+        );
+        let indices = pool.iter().map(|&index| index as usize);
+        Ok(DeckAccount {
+            owner: self.owner,
+            nfts: indices.clone().map(|i| self.nfts[i]).collect(),
+            bump: self.bump,
+            stats: indices.clone().map(|i| self.stats[i]).collect(),
+            locked_game: self.locked_game,
+            collection: self.collection,
+            standards: indices.map(|i| self.standards[i]).collect(),
+            locked_pool: self.locked_pool.clone(),
+        })
+    }
+
+    /// A shuffled game deals from a pool as large as the deck allows, so a
+    /// player can't narrow the shuffle down to the hand they want.
+    pub fn check_deal_pool(&self, pool: &[u16]) -> Result<()> {
+        require!(
+            pool.len() == self.nfts.len().min(MAX_DECK_SIZE),
+            CustomError::DealPoolTooSmall
+        );
+        Ok(())
+    }
+
+    /// Powers and elements of the deck, zero padded to `MAX_DECK_SIZE`.
+    pub fn padded_stats(&self) -> ([u8; MAX_DECK_SIZE], [u8; MAX_DECK_SIZE]) {
+        let mut powers = [0u8; MAX_DECK_SIZE];
+        let mut elements = [0u8; MAX_DECK_SIZE];
+        for (i, stats) in self.stats.iter().take(MAX_DECK_SIZE).enumerate() {
+            powers[i] = stats.power;
+            elements[i] = stats.element;
+        }
+        (powers, elements)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked_game != Pubkey::default()
+    }
+
+    /// Adds a card, keeping `collection` set only while all cards share it.
+    pub fn push_card(
+        &mut self,
+        mint: Pubkey,
+        standard: AssetStandard,
+        stats: CardStats,
+        collection: Pubkey,
+    ) -> Result<()> {
+        require!(!self.nfts.contains(&mint), CustomError::DuplicateCard);
+        if self.nfts.is_empty() {
+            self.collection = collection;
+        } else if self.collection != collection {
+            self.collection = Pubkey::default();
+        }
+        self.nfts.push(mint);
+        self.standards.push(standard);
+        self.stats.push(stats);
+        Ok(())
+    }
+
+    /// Deck indices and mints of the mpl-core cards, the only ones the program
+    /// can freeze or escrow.
+    pub fn core_cards(&self) -> impl Iterator<Item = (usize, &Pubkey)> {
+        self.nfts
+            .iter()
+            .zip(self.standards.iter())
+            .enumerate()
+            .filter(|(_, (_, standard))| **standard == AssetStandard::Core)
+            .map(|(i, (mint, _))| (i, mint))
+    }
+}
+
+// Token standard a deck card is held under
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Default)]
+pub enum AssetStandard {
+    // mpl-core asset; the card's key is the asset account
+    #[default]
+    Core,
+    // Token Metadata NFT; the card's key is the mint
+    TokenMetadata,
+    // Bubblegum compressed NFT; the card's key is the asset id
+    Compressed,
+}
+
+/// Where a compressed card sits in its merkle tree, as the DAS `getAsset` and
+/// `getAssetProof` calls report it. The proof nodes themselves are passed as
+/// accounts after the tree.
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub struct CompressedProof {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub delegate: Pubkey,
+    pub nonce: u64,
+    pub index: u32,
+    pub proof_len: u8,
+}
+
+/// Which decks may play. Every deck must follow `DeckRules::DEFAULT`; a market
+/// can narrow it for the decks joining its game. Sizes bound the pool a deck
+/// joins with.
+///
+/// Card stats are whatever the card's update authority wrote, so the default
+/// rules trust every collection only as far as the stat bounds below. A market
+/// that should only play stats it trusts names their collection.
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub struct DeckRules {
+    pub min_size: u8,
+    pub max_size: u8,
+    // Collection every card must belong to; default allows any
+    pub collection: Pubkey,
+}
+
+impl DeckRules {
+    // A hand is three distinct cards, so no deck can hold fewer
+    pub const DEFAULT: DeckRules = DeckRules {
+        min_size: 3,
+        max_size: MAX_DECK_SIZE as u8,
+        collection: Pubkey::new_from_array([0; 32]),
+    };
+
+    /// Rules must stay within the default bounds.
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.min_size >= Self::DEFAULT.min_size
+                && self.min_size <= self.max_size
+                && self.max_size <= Self::DEFAULT.max_size,
+            CustomError::InvalidDeckRules
+        );
+        Ok(())
+    }
+
+    pub fn check_size(&self, size: usize) -> Result<()> {
+        require!(
+            size >= self.min_size as usize && size <= self.max_size as usize,
+            CustomError::InvalidDeckSize
+        );
+        Ok(())
+    }
+
+    /// Mints are kept unique as cards are added, and only mpl-core cards get
+    /// past `check_deck_owned`, so only size, stats and collection need
+    /// checking here.
+    pub fn check(&self, deck: &DeckAccount) -> Result<()> {
+        self.check_size(deck.nfts.len())?;
+        require!(
+            deck.stats.iter().all(|stats| {
+                stats.power <= MAX_CARD_POWER
+                    && stats.element <= ELEMENT_EARTH
+                    && stats.rarity <= MAX_CARD_RARITY
+            }),
+            CustomError::InvalidCardAttributes
+        );
+        require!(
+            self.collection == Pubkey::default() || deck.collection == self.collection,
+            CustomError::CollectionNotApproved
+        );
+        Ok(())
+    }
+}
+
+// Card elements used by the battle circuit
+pub const ELEMENT_NONE: u8 = 0;
+pub const ELEMENT_FIRE: u8 = 1;
+pub const ELEMENT_WATER: u8 = 2;
+pub const ELEMENT_EARTH: u8 = 3;
+
+// Bounds of the stats a card can play with
+pub const MAX_CARD_POWER: u8 = 10;
+pub const MAX_CARD_RARITY: u8 = 4;
+
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Default)]
+pub struct CardStats {
+    pub power: u8,
+    pub element: u8,
+    pub rarity: u8,
 }
 
 #[derive(InitSpace)]
@@ -73,11 +299,321 @@ pub struct GameAccount {
     pub no_cards1: [u8; 32],
     pub no_cards2: [u8; 32],
     pub no_cards3: [u8; 32],
+    pub yes_choice: [u8; 32],
+    pub no_choice: [u8; 32],
+    // Encrypted power 1-3 then element 1-3 of each side's cards
+    pub yes_stats: [[u8; 32]; 6],
+    pub no_stats: [[u8; 32]; 6],
+    pub yes_joined: [u8; 32],
+    pub no_joined: [u8; 32],
+    // Encrypted slot played in each round, kept for the post-game reveal
+    pub yes_rounds: [[u8; 32]; 3],
+    pub no_rounds: [[u8; 32]; 3],
+    // Encrypted mask of the slots each side has already played
+    pub yes_used: [u8; 32],
+    pub no_used: [u8; 32],
     pub current_turn: u8,
     pub result: u8,
     pub bump: u8,
     pub nonce: u128,
     pub game_id: u64,
+    pub yes_played: bool,
+    pub no_played: bool,
+    pub yes_wins: u8,
+    pub no_wins: u8,
+    // Unix timestamp after which the current phase can be forfeited
+    pub deadline: i64,
+    // Last computation queued for this game; only one runs at a time since each
+    // one reads and rewrites the whole encrypted state
+    pub computation: PendingComputation,
+    // Bumped on every failed or refused computation so a retry gets a fresh offset
+    pub failed_computations: u32,
+    // x25519 keys the players registered at join; their views are encrypted to them
+    pub yes_view_key: [u8; 32],
+    pub no_view_key: [u8; 32],
+    // Revealed winner of each round, ROUND_PENDING for rounds not played
+    pub round_winners: [u8; 3],
+    pub deal_mode: DealMode,
+    // Ante mode: each side escrows a deck card that the winner takes
+    pub ante: bool,
+    pub yes_ante: AnteStake,
+    pub no_ante: AnteStake,
+    // Pool of each side's deck the game plays from (see `DeckAccount::select`)
+    #[max_len(20)]
+    pub yes_deck_pool: Vec<u16>,
+    #[max_len(20)]
+    pub no_deck_pool: Vec<u16>,
+}
+
+// A card held in escrow by the game account, and who put it there
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq, Default)]
+pub struct AnteStake {
+    pub asset: Pubkey,
+    pub owner: Pubkey,
+}
+
+// How players get their three cards: picked from their deck, or dealt by the MXE
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum DealMode {
+    Chosen,
+    Shuffled,
+}
+
+/// Plaintext record of a finished game, written by the `reveal_game` computation.
+/// Card slots hold indices into the deck pool played; rounds hold the slot (1-3)
+/// played, 0 if none.
+#[derive(InitSpace)]
+#[account]
+pub struct GameHistory {
+    pub game_account: Pubkey,
+    pub market_account: Pubkey,
+    pub player_yes: Pubkey,
+    pub player_yes_deck: Pubkey,
+    pub player_no: Pubkey,
+    pub player_no_deck: Pubkey,
+    pub yes_cards: [u8; 3],
+    pub no_cards: [u8; 3],
+    pub yes_powers: [u8; 3],
+    pub yes_elements: [u8; 3],
+    pub no_powers: [u8; 3],
+    pub no_elements: [u8; 3],
+    pub yes_rounds: [u8; 3],
+    pub no_rounds: [u8; 3],
+    pub round_winners: [u8; 3],
+    pub result: u8,
+    pub revealed: bool,
+    pub revealed_at: i64,
+    pub bump: u8,
+    #[max_len(20)]
+    pub yes_deck_pool: Vec<u16>,
+    #[max_len(20)]
+    pub no_deck_pool: Vec<u16>,
+}
+
+impl GameHistory {
+    /// Fills the record from the `reveal_game` output, laid out as the circuit
+    /// documents it.
+    pub fn set_revealed(&mut self, record: [u8; 24], timestamp: i64) {
+        let part = |i: usize| -> [u8; 3] { record[i * 3..i * 3 + 3].try_into().unwrap() };
+        self.yes_cards = part(0);
+        self.no_cards = part(1);
+        self.yes_powers = part(2);
+        self.yes_elements = part(3);
+        self.no_powers = part(4);
+        self.no_elements = part(5);
+        self.yes_rounds = part(6);
+        self.no_rounds = part(7);
+        self.revealed = true;
+        self.revealed_at = timestamp;
+    }
+}
+
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum ComputationKind {
+    InitGame,
+    JoinGame,
+    PlayTurn,
+    RevealGame,
+}
+
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum ComputationStatus {
+    Pending,
+    Finalized,
+    Failed,
+}
+
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub struct PendingComputation {
+    pub offset: u64,
+    pub kind: ComputationKind,
+    pub queued_slot: u64,
+    pub status: ComputationStatus,
+    pub side: u8, // 0 = yes, 1 = no; side whose join or turn was queued
+}
+
+// Round winners revealed by `play_turn`, also used for `GameAccount.result`
+pub const ROUND_PENDING: u8 = 0;
+pub const ROUND_YES: u8 = 1;
+pub const ROUND_NO: u8 = 2;
+pub const ROUND_DRAW: u8 = 3;
+// `GameAccount.result` of a game forfeited before anyone joined
+pub const GAME_CANCELLED: u8 = 4;
+
+impl GameAccount {
+    pub const ROUNDS: u8 = 3;
+    // Seconds both players have to join, then to play each turn
+    pub const JOIN_TIMEOUT: i64 = 24 * 60 * 60;
+    pub const TURN_TIMEOUT: i64 = 60 * 60;
+    // Slots after which a computation without callback can be rolled back
+    pub const COMPUTATION_TIMEOUT_SLOTS: u64 = 300;
+
+    // The `GameMoves` ciphertexts start after the discriminator and the five pubkeys
+    pub const ENCRYPTED_STATE_OFFSET: u32 = 8 + 32 * 5;
+    pub const ENCRYPTED_STATE_LEN: u32 = 32 * 30;
+    // Fields of the circuits' `PlayerView`
+    pub const PLAYER_VIEW_LEN: usize = 11;
+
+    pub fn set_encrypted_state(&mut self, ciphertexts: [[u8; 32]; 30], nonce: u128) {
+        self.yes_cards1 = ciphertexts[0];
+        self.yes_cards2 = ciphertexts[1];
+        self.yes_cards3 = ciphertexts[2];
+        self.no_cards1 = ciphertexts[3];
+        self.no_cards2 = ciphertexts[4];
+        self.no_cards3 = ciphertexts[5];
+        self.yes_choice = ciphertexts[6];
+        self.no_choice = ciphertexts[7];
+        self.yes_stats.copy_from_slice(&ciphertexts[8..14]);
+        self.no_stats.copy_from_slice(&ciphertexts[14..20]);
+        self.yes_joined = ciphertexts[20];
+        self.no_joined = ciphertexts[21];
+        self.yes_rounds.copy_from_slice(&ciphertexts[22..25]);
+        self.no_rounds.copy_from_slice(&ciphertexts[25..28]);
+        self.yes_used = ciphertexts[28];
+        self.no_used = ciphertexts[29];
+        self.nonce = nonce;
+    }
+
+    /// Computation offset of a game step, derived from the game, the step and the
+    /// number of failures before it so that every queued computation of a game
+    /// can be found again from the game account alone.
+    pub fn computation_offset(
+        game: &Pubkey,
+        kind: ComputationKind,
+        turn: u8,
+        side: u8,
+        failed_computations: u32,
+    ) -> u64 {
+        let hash = anchor_lang::solana_program::hash::hashv(&[
+            b"computation",
+            game.as_ref(),
+            &[kind as u8, turn, side],
+            &failed_computations.to_le_bytes(),
+        ]);
+        u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+    }
+
+    /// Offset of an `add_together` computation, derived from its payer and
+    /// nonce under its own domain so it can't take the offset a game step needs.
+    pub fn add_together_offset(payer: &Pubkey, nonce: u128) -> u64 {
+        let hash = anchor_lang::solana_program::hash::hashv(&[
+            b"add_together",
+            payer.as_ref(),
+            &nonce.to_le_bytes(),
+        ]);
+        u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+    }
+
+    /// Offset the next `kind` computation queued by `side` on this game must use.
+    pub fn next_offset(&self, game: &Pubkey, kind: ComputationKind, side: u8) -> u64 {
+        Self::computation_offset(game, kind, self.current_turn, side, self.failed_computations)
+    }
+
+    pub fn queue_computation(&mut self, kind: ComputationKind, offset: u64, side: u8, slot: u64) {
+        self.computation = PendingComputation {
+            offset,
+            kind,
+            queued_slot: slot,
+            status: ComputationStatus::Pending,
+            side,
+        };
+    }
+
+    /// Whether a callback for `kind` is still expected. A callback arriving after
+    /// its computation was rolled back must not touch the game.
+    pub fn awaits(&self, kind: ComputationKind) -> bool {
+        self.computation.status == ComputationStatus::Pending && self.computation.kind == kind
+    }
+
+    /// Checks that a callback belongs to the computation queued on this game. The
+    /// circuits echo the offset they were queued with, so an output delivered with
+    /// another game's account is refused instead of overwriting its state.
+    pub fn verify_callback(&self, computation_offset: u64) -> Result<()> {
+        require!(
+            self.computation.offset == computation_offset,
+            CustomError::CallbackMismatch
+        );
+        Ok(())
+    }
+
+    /// Checks that `payer` joins with their own bet on this game's market and
+    /// with their own deck.
+    pub fn check_seat(&self, bet: &BetAccount, deck: &DeckAccount, payer: &Pubkey) -> Result<()> {
+        require_keys_eq!(bet.user, *payer, CustomError::InvalidPayer);
+        require_keys_eq!(
+            bet.market_account,
+            self.market_account,
+            CustomError::GameMarketMismatch
+        );
+        require_keys_eq!(deck.owner, *payer, CustomError::InvalidDeckOwner);
+        Ok(())
+    }
+
+    pub fn computation_timed_out(&self, slot: u64) -> bool {
+        self.computation.status == ComputationStatus::Pending
+            && slot > self.computation.queued_slot + Self::COMPUTATION_TIMEOUT_SLOTS
+    }
+
+    pub fn player(&self, side: u8) -> Pubkey {
+        if side == 0 {
+            self.player_yes
+        } else {
+            self.player_no
+        }
+    }
+
+    pub fn ante_stake_mut(&mut self, side: u8) -> &mut AnteStake {
+        if side == 0 {
+            &mut self.yes_ante
+        } else {
+            &mut self.no_ante
+        }
+    }
+
+    /// Who the ante escrowed by `side` goes to now: the winner once the game
+    /// has one, its owner on a draw or cancel, or its owner once their join is
+    /// undone. None while it has to stay in escrow.
+    pub fn ante_recipient(&self, side: u8) -> Option<Pubkey> {
+        let stake = if side == 0 { self.yes_ante } else { self.no_ante };
+        if stake.asset == Pubkey::default() {
+            return None;
+        }
+        match self.result {
+            ROUND_PENDING => (self.player(side) != stake.owner).then_some(stake.owner),
+            ROUND_YES if self.player_yes != Pubkey::default() => Some(self.player_yes),
+            ROUND_NO if self.player_no != Pubkey::default() => Some(self.player_no),
+            _ => Some(stake.owner),
+        }
+    }
+
+    /// Frees `side` after a join that failed or was refused.
+    pub fn leave(&mut self, side: u8) {
+        if side == 0 {
+            self.player_yes = Pubkey::default();
+            self.player_yes_deck = Pubkey::default();
+            self.yes_deck_pool.clear();
+            self.yes_view_key = [0; 32];
+        } else {
+            self.player_no = Pubkey::default();
+            self.player_no_deck = Pubkey::default();
+            self.no_deck_pool.clear();
+            self.no_view_key = [0; 32];
+        }
+    }
+
+    /// Undoes the plaintext changes made when the computation was queued, so the
+    /// turn can simply be sent again, or the join once its deck is released.
+    pub fn fail_computation(&mut self) {
+        let side = self.computation.side;
+        match self.computation.kind {
+            ComputationKind::InitGame | ComputationKind::RevealGame => {}
+            ComputationKind::JoinGame => self.leave(side),
+            ComputationKind::PlayTurn if side == 0 => self.yes_played = false,
+            ComputationKind::PlayTurn => self.no_played = false,
+        }
+        self.computation.status = ComputationStatus::Failed;
+        self.failed_computations += 1;
+    }
 }
 
 #[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
@@ -88,6 +624,14 @@ pub enum Status {
     ResolvedNo,
     Completed,
     Cancelled,
+    Frozen,
+}
+
+// How a market gets its outcome: `resolve` with a value, or the linked game's result
+#[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Copy, Clone, PartialEq)]
+pub enum ResolutionMode {
+    Value,
+    Game,
 }
 
 #[derive(InitSpace, AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
@@ -105,4 +649,6 @@ pub struct CreateMarketParams {
     pub target_value: u64,
     pub required_bet_amount: u64,
     pub max_player_count: u64,
+    pub resolution_mode: ResolutionMode,
+    pub deck_rules: DeckRules,
 }
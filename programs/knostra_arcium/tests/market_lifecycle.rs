@@ -0,0 +1,1723 @@
+//! Market lifecycle tests on an in-process runtime.
+//!
+//! These load the compiled program from `target/deploy`, so run `arcium build`
+//! (or `anchor build`) before `cargo test`. Freezing cards runs against
+//! mpl-core itself, the build pinned in `tests/fixtures` (see `MPL_CORE_SO`).
+//! Joins freeze a deck only once the Arcium accounts load, so that freeze is
+//! asserted in the localnet suite.
+//!
+//! Not covered here: `PlayerAlreadyJoined` needs a live MXE and the Arcium
+//! program, so it can only be driven from the localnet suite in
+//! `tests/knostra_arcium.ts`, as can settling a game-mode market from its game
+//! result.
+//! Compressed cards need account compression loaded to verify their proofs,
+//! so only their argument checks run here.
+//! `MarketNotEnded`, `MarketNotStarted`, `CannotCancelMarket`, `MathOverflow`,
+//! `InsufficientTreasury`, `MaxPlayersReached` and `UnauthorizedResolver` have
+//! no reachable path in the program today.
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator, ToAccountMetas};
+use arcium_anchor::prelude::{comp_def_offset, ComputationDefinitionAccount, ARCIUM_PROG_ID};
+use anchor_spl::token::spl_token;
+use knostra_arcium::errors::CustomError;
+use knostra_arcium::instructions::check_deck_owned;
+use knostra_arcium::state::{
+    AnteStake, AssetStandard, BetAccount, CardStats, CompressedProof, ComputationKind,
+    ComputationStatus, CreateMarketParams, DealMode, DeckAccount, DeckRules, GameAccount,
+    GameHistory, PendingComputation, ResolutionMode, Status, TreasuryAccount, ELEMENT_WATER,
+    GAME_CANCELLED, MAX_CARD_POWER, MAX_DECK_CARDS, MAX_DECK_SIZE, ROUND_PENDING, ROUND_YES,
+};
+use knostra_client::instructions::DeckCard;
+use knostra_client::transaction::{self, JOIN_COMPUTE_UNITS};
+use knostra_client::{accounts, instructions, pda, PROGRAM_ID};
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use mpl_core::accounts::{BaseAssetV1, PluginHeaderV1, PluginRegistryV1};
+use mpl_core::instructions::{CreateCollectionV1Builder, CreateV1Builder, TransferV1Builder};
+use mpl_core::types::{
+    Attribute, Attributes, DataState, FreezeDelegate, Key, Plugin, PluginAuthority,
+    PluginAuthorityPair, PluginType, RegistryRecord, UpdateAuthority,
+};
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::types::{Collection, Key as MetadataKey, TokenStandard};
+use solana_sdk::account::Account;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::clock::Clock;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+
+const BET: u64 = LAMPORTS_PER_SOL;
+const SEED: u64 = 1;
+
+fn market_params(relational_value: &str, resolution_mode: ResolutionMode) -> CreateMarketParams {
+    CreateMarketParams {
+        name: "BTC market".to_string(),
+        description: "Will BTC >= 120k today?".to_string(),
+        token: "BTC".to_string(),
+        market_start: 0,
+        market_end: 3600,
+        relational_value: relational_value.to_string(),
+        target_value: 120_000,
+        required_bet_amount: BET,
+        max_player_count: 1,
+        resolution_mode,
+        deck_rules: DeckRules::DEFAULT,
+    }
+}
+
+struct Harness {
+    svm: LiteSVM,
+    creator: Keypair,
+    yes: Keypair,
+    no: Keypair,
+    market: Pubkey,
+}
+
+impl Harness {
+    fn new(relational_value: &str) -> Self {
+        Self::with_mode(relational_value, ResolutionMode::Value)
+    }
+
+    fn with_mode(relational_value: &str, resolution_mode: ResolutionMode) -> Self {
+        let mut svm = LiteSVM::new();
+        let so = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/knostra_arcium.so");
+        svm.add_program_from_file(PROGRAM_ID, so)
+            .expect("program not built, run `arcium build` first");
+
+        let creator = Keypair::new();
+        let yes = Keypair::new();
+        let no = Keypair::new();
+        for user in [&creator, &yes, &no] {
+            svm.airdrop(&user.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+        }
+
+        let mut harness = Self {
+            svm,
+            market: pda::market(&creator.pubkey(), SEED).0,
+            creator,
+            yes,
+            no,
+        };
+        let params = market_params(relational_value, resolution_mode);
+        let creator = harness.creator.insecure_clone();
+        harness
+            .send(instructions::create(creator.pubkey(), SEED, params), &creator)
+            .unwrap();
+        harness
+    }
+
+    fn send(&mut self, ix: Instruction, signer: &Keypair) -> TransactionResult {
+        self.svm.expire_blockhash();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx)
+    }
+
+    fn bet(&mut self, choice: bool, amount: u64) -> TransactionResult {
+        let user = if choice { self.yes.insecure_clone() } else { self.no.insecure_clone() };
+        self.send(instructions::bet(self.market, user.pubkey(), amount, choice), &user)
+    }
+
+    fn claim(&mut self, user: &Keypair) -> TransactionResult {
+        self.send(instructions::claim(self.market, user.pubkey()), user)
+    }
+
+    fn resolve(&mut self, value: u64) -> TransactionResult {
+        let payer = self.creator.insecure_clone();
+        self.send(
+            instructions::resolve(self.market, payer.pubkey(), value),
+            &payer,
+        )
+    }
+
+    fn treasury(&self) -> TreasuryAccount {
+        let account = self.svm.get_account(&pda::treasury(&self.market).0).unwrap();
+        accounts::treasury_account(&account.data).unwrap()
+    }
+
+    fn market_status(&self) -> Status {
+        let account = self.svm.get_account(&self.market).unwrap();
+        accounts::market_account(&account.data).unwrap().status
+    }
+
+    fn vault_balance(&self) -> u64 {
+        self.svm
+            .get_balance(&pda::treasury_vault(&self.market).0)
+            .unwrap_or(0)
+    }
+
+    fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    fn warp(&mut self, seconds: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Writes the market's game account directly; games are created through the MXE.
+    fn set_game(&mut self, game: &GameAccount) {
+        let mut data = Vec::new();
+        game.try_serialize(&mut data).unwrap();
+        self.set_data(pda::game(&game.market_account).0, PROGRAM_ID, data);
+    }
+
+    fn set_data(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    fn game(&self) -> GameAccount {
+        let account = self.svm.get_account(&pda::game(&self.market).0).unwrap();
+        accounts::game_account(&account.data).unwrap()
+    }
+
+    fn assert_treasury(&self, total: u64, fee: u64, creator_fee: u64, yes: u64, no: u64) {
+        let treasury = self.treasury();
+        assert_eq!(treasury.total_amount, total, "total_amount");
+        assert_eq!(treasury.fee_amount, fee, "fee_amount");
+        assert_eq!(treasury.creator_fee_amount, creator_fee, "creator_fee_amount");
+        assert_eq!(treasury.yes_count, yes, "yes_count");
+        assert_eq!(treasury.no_count, no, "no_count");
+        assert!(treasury.status == self.market_status(), "status out of sync");
+    }
+}
+
+fn assert_custom_error(result: TransactionResult, expected: CustomError) {
+    let err = result.expect_err("transaction should have failed").err;
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::Custom(expected.into()))
+    );
+}
+
+fn assert_anchor_error<T>(result: anchor_lang::Result<T>, expected: CustomError) {
+    match result {
+        Err(anchor_lang::error::Error::AnchorError(err)) => {
+            assert_eq!(err.error_code_number, u32::from(expected))
+        }
+        Err(other) => panic!("unexpected error: {other:?}"),
+        Ok(_) => panic!("check should have failed"),
+    }
+}
+
+/// Bets on both sides so the market starts, then resolves it to yes.
+fn resolved_market() -> Harness {
+    let mut h = Harness::new(">=");
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+    h.resolve(130_000).unwrap();
+    h
+}
+
+#[test]
+fn full_lifecycle_pays_winner_and_creator() {
+    let mut h = Harness::new(">=");
+    assert!(h.market_status() == Status::NotStarted);
+    assert_eq!(h.vault_balance(), 0);
+    h.assert_treasury(0, 0, 0, 0, 0);
+
+    h.bet(true, BET).unwrap();
+    assert!(h.market_status() == Status::NotStarted);
+    assert_eq!(h.vault_balance(), BET);
+    h.assert_treasury(BET, 0, 0, 1, 0);
+
+    // The second side filling up starts the market.
+    h.bet(false, BET).unwrap();
+    assert!(h.market_status() == Status::Ongoing);
+    assert_eq!(h.vault_balance(), 2 * BET);
+    h.assert_treasury(2 * BET, 0, 0, 1, 1);
+
+    h.resolve(130_000).unwrap();
+    assert!(h.market_status() == Status::ResolvedYes);
+    assert_eq!(h.vault_balance(), 2 * BET);
+    h.assert_treasury(2 * BET, 0, 0, 1, 1);
+
+    let payout = 2 * BET;
+    let creator_fee = payout * 100 / 10_000;
+    let protocol_fee = payout * 100 / 10_000;
+    let total_fee = creator_fee + protocol_fee;
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+    assert_eq!(h.vault_balance(), total_fee);
+    h.assert_treasury(0, protocol_fee, creator_fee, 1, 1);
+
+    let creator = h.creator.insecure_clone();
+    h.send(instructions::claim_fees(h.market, creator.pubkey()), &creator)
+        .unwrap();
+    assert_eq!(h.vault_balance(), total_fee - creator_fee);
+    h.assert_treasury(0, protocol_fee, 0, 1, 1);
+}
+
+#[test]
+fn cancelled_market_refunds_bettors() {
+    let mut h = Harness::new(">=");
+    h.bet(true, BET).unwrap();
+
+    let creator = h.creator.pubkey();
+    let payer = h.yes.insecure_clone();
+    h.send(instructions::cancel(h.market, creator), &payer).unwrap();
+    assert!(h.market_status() == Status::Cancelled);
+    assert_eq!(h.vault_balance(), BET);
+    h.assert_treasury(BET, 0, 0, 1, 0);
+
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+    assert_eq!(h.vault_balance(), 0);
+    h.assert_treasury(0, 0, 0, 1, 0);
+
+    assert_custom_error(h.claim(&yes), CustomError::AlreadyClaimed);
+}
+
+#[test]
+fn bet_with_wrong_amount_fails() {
+    let mut h = Harness::new(">=");
+    assert_custom_error(h.bet(true, BET / 10), CustomError::InvalidBetAmount);
+    assert_eq!(h.vault_balance(), 0);
+    h.assert_treasury(0, 0, 0, 0, 0);
+}
+
+#[test]
+fn bet_after_start_fails() {
+    let mut h = Harness::new(">=");
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    let late = Keypair::new();
+    h.svm.airdrop(&late.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    let result = h.send(instructions::bet(h.market, late.pubkey(), BET, true), &late);
+    assert_custom_error(result, CustomError::InvalidMarketStatus);
+    assert_eq!(h.vault_balance(), 2 * BET);
+}
+
+#[test]
+fn claim_before_resolution_fails() {
+    let mut h = Harness::new(">=");
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    let yes = h.yes.insecure_clone();
+    assert_custom_error(h.claim(&yes), CustomError::InvalidMarketStatus);
+}
+
+#[test]
+fn cancel_after_start_fails() {
+    let mut h = Harness::new(">=");
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    let creator = h.creator.insecure_clone();
+    let result = h.send(instructions::cancel(h.market, creator.pubkey()), &creator);
+    assert_custom_error(result, CustomError::InvalidMarketStatus);
+}
+
+#[test]
+fn resolve_with_invalid_operator_fails() {
+    let mut h = Harness::new("!=");
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    assert_custom_error(h.resolve(130_000), CustomError::InvalidRelationalOp);
+    assert!(h.market_status() == Status::Ongoing);
+}
+
+#[test]
+fn game_market_cannot_be_resolved_by_value() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    assert_custom_error(h.resolve(130_000), CustomError::ResolutionModeMismatch);
+    assert!(h.market_status() == Status::Ongoing);
+}
+
+#[test]
+fn losing_bet_cannot_claim() {
+    let mut h = resolved_market();
+    let no = h.no.insecure_clone();
+    assert_custom_error(h.claim(&no), CustomError::NotAWinner);
+    assert_eq!(h.vault_balance(), 2 * BET);
+}
+
+#[test]
+fn winning_bet_cannot_claim_twice() {
+    let mut h = resolved_market();
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+    let vault = h.vault_balance();
+    assert_custom_error(h.claim(&yes), CustomError::AlreadyClaimed);
+    assert_eq!(h.vault_balance(), vault);
+}
+
+#[test]
+fn claim_fees_requires_fees_and_creator() {
+    let mut h = resolved_market();
+    let creator = h.creator.insecure_clone();
+    let result = h.send(instructions::claim_fees(h.market, creator.pubkey()), &creator);
+    assert_custom_error(result, CustomError::NoFeesToClaim);
+
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+    let result = h.send(instructions::claim_fees(h.market, yes.pubkey()), &yes);
+    assert_custom_error(result, CustomError::Unauthorized);
+}
+
+/// Writes an mpl-core asset owned by `owner`, with an Attributes plugin when
+/// `attributes` is given.
+fn set_asset(svm: &mut LiteSVM, owner: &Pubkey, attributes: Option<&[(&str, &str)]>) -> Pubkey {
+    set_asset_in(svm, owner, UpdateAuthority::None, attributes)
+}
+
+fn set_asset_in(
+    svm: &mut LiteSVM,
+    owner: &Pubkey,
+    update_authority: UpdateAuthority,
+    attributes: Option<&[(&str, &str)]>,
+) -> Pubkey {
+    write_asset(svm, owner, update_authority, attributes, PluginAuthority::UpdateAuthority)
+}
+
+fn write_asset(
+    svm: &mut LiteSVM,
+    owner: &Pubkey,
+    update_authority: UpdateAuthority,
+    attributes: Option<&[(&str, &str)]>,
+    attributes_authority: PluginAuthority,
+) -> Pubkey {
+    let asset = Pubkey::new_unique();
+    let mut data = Vec::new();
+    BaseAssetV1 {
+        key: Key::AssetV1,
+        owner: *owner,
+        update_authority,
+        name: "Card".to_string(),
+        uri: String::new(),
+        seq: None,
+    }
+    .serialize(&mut data)
+    .unwrap();
+
+    if let Some(attributes) = attributes {
+        // Layout: asset | plugin header | plugin | plugin registry
+        let plugin_offset = data.len() as u64 + 9;
+        let mut plugin = Vec::new();
+        Plugin::Attributes(Attributes {
+            attribute_list: attributes
+                .iter()
+                .map(|(key, value)| Attribute {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+        })
+        .serialize(&mut plugin)
+        .unwrap();
+
+        PluginHeaderV1 {
+            key: Key::PluginHeaderV1,
+            plugin_registry_offset: plugin_offset + plugin.len() as u64,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        data.extend(plugin);
+        PluginRegistryV1 {
+            key: Key::PluginRegistryV1,
+            registry: vec![RegistryRecord {
+                plugin_type: PluginType::Attributes,
+                authority: attributes_authority,
+                offset: plugin_offset,
+            }],
+            external_registry: vec![],
+        }
+        .serialize(&mut data)
+        .unwrap();
+    }
+
+    svm.set_account(
+        asset,
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: mpl_core::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+    asset
+}
+
+const CARD: &[(&str, &str)] = &[("power", "7"), ("element", "water"), ("rarity", "rare")];
+
+#[test]
+fn create_deck_checks_assets() {
+    let mut h = Harness::new(">=");
+    let player = h.yes.insecure_clone();
+    let owned = set_asset(&mut h.svm, &player.pubkey(), Some(CARD));
+    let foreign = set_asset(&mut h.svm, &h.no.pubkey(), Some(CARD));
+    let plain = set_asset(&mut h.svm, &player.pubkey(), None);
+    let bad_power = set_asset(&mut h.svm, &player.pubkey(), Some(&[("power", "strong")]));
+    let bad_element = set_asset(&mut h.svm, &player.pubkey(), Some(&[("element", "4")]));
+    let bad_rarity = set_asset(&mut h.svm, &player.pubkey(), Some(&[("rarity", "5")]));
+    // Attributes its holder can rewrite
+    let owner_managed = write_asset(
+        &mut h.svm,
+        &player.pubkey(),
+        UpdateAuthority::None,
+        Some(CARD),
+        PluginAuthority::Owner,
+    );
+    let strong = set_asset(&mut h.svm, &player.pubkey(), Some(&[("power", "255")]));
+    // Decks need three cards; these fill the rest.
+    let a = set_asset(&mut h.svm, &player.pubkey(), Some(CARD));
+    let b = set_asset(&mut h.svm, &player.pubkey(), Some(CARD));
+
+    let result = h.send(
+        instructions::create_deck(player.pubkey(), 1, vec![a, b, foreign]),
+        &player,
+    );
+    assert_custom_error(result, CustomError::NotNftOwner);
+
+    // Asset account passed for a different mint.
+    let mut ix = instructions::create_deck(player.pubkey(), 1, vec![a, b, owned]);
+    ix.accounts.last_mut().unwrap().pubkey = foreign;
+    assert_custom_error(h.send(ix, &player), CustomError::InvalidMint);
+
+    for asset in [plain, bad_power, bad_element, bad_rarity, owner_managed] {
+        let result = h.send(
+            instructions::create_deck(player.pubkey(), 1, vec![a, b, asset]),
+            &player,
+        );
+        assert_custom_error(result, CustomError::InvalidCardAttributes);
+    }
+
+    let result = h.send(instructions::create_deck(player.pubkey(), 1, vec![a, b]), &player);
+    assert_custom_error(result, CustomError::InvalidDeckSize);
+    let result = h.send(
+        instructions::create_deck(player.pubkey(), 1, vec![a, b, a]),
+        &player,
+    );
+    assert_custom_error(result, CustomError::DuplicateCard);
+
+    h.send(
+        instructions::create_deck(player.pubkey(), 1, vec![a, b, owned]),
+        &player,
+    )
+    .unwrap();
+    let deck = h.svm.get_account(&pda::deck(&player.pubkey(), 1).0).unwrap();
+    let deck = accounts::deck_account(&deck.data).unwrap();
+    assert_eq!(deck.nfts, vec![a, b, owned]);
+    assert!(deck.stats[2] == CardStats { power: 7, element: ELEMENT_WATER, rarity: 2 });
+
+    // Power beyond the game's range plays at the cap
+    h.send(
+        instructions::create_deck(player.pubkey(), 2, vec![a, b, strong]),
+        &player,
+    )
+    .unwrap();
+    let deck = h.svm.get_account(&pda::deck(&player.pubkey(), 2).0).unwrap();
+    let deck = accounts::deck_account(&deck.data).unwrap();
+    assert_eq!(deck.stats[2].power, MAX_CARD_POWER);
+}
+
+#[test]
+fn deck_rules_bound_market_decks() {
+    let mut h = Harness::new(">=");
+    let creator = h.creator.insecure_clone();
+    let player = h.yes.insecure_clone();
+    let collection = Pubkey::new_unique();
+
+    let mut params = CreateMarketParams {
+        deck_rules: DeckRules {
+            min_size: 2,
+            ..DeckRules::DEFAULT
+        },
+        ..market_params(">=", ResolutionMode::Value)
+    };
+    let result = h.send(instructions::create(creator.pubkey(), 2, params.clone()), &creator);
+    assert_custom_error(result, CustomError::InvalidDeckRules);
+    params.deck_rules = DeckRules {
+        min_size: 3,
+        max_size: 4,
+        collection,
+    };
+    h.send(instructions::create(creator.pubkey(), 2, params.clone()), &creator)
+        .unwrap();
+
+    let in_collection: Vec<Pubkey> = (0..4)
+        .map(|_| {
+            set_asset_in(
+                &mut h.svm,
+                &player.pubkey(),
+                UpdateAuthority::Collection(collection),
+                Some(CARD),
+            )
+        })
+        .collect();
+    let stray = set_asset(&mut h.svm, &player.pubkey(), Some(CARD));
+
+    h.send(
+        instructions::create_deck(player.pubkey(), 1, in_collection[..3].to_vec()),
+        &player,
+    )
+    .unwrap();
+    let address = pda::deck(&player.pubkey(), 1).0;
+    let mut deck = h.deck(&address);
+    assert_eq!(deck.collection, collection);
+    params.deck_rules.check(&deck).unwrap();
+
+    // Joins check the market's rules; the check runs directly since joins
+    // need the MXE.
+    deck.nfts.push(in_collection[3]);
+    deck.nfts.push(stray);
+    assert_anchor_error(params.deck_rules.check(&deck), CustomError::InvalidDeckSize);
+
+    h.send(instructions::add_deck_cards(player.pubkey(), address, vec![stray]), &player)
+        .unwrap();
+    let mut deck = h.deck(&address);
+    assert_eq!(deck.collection, Pubkey::default());
+    assert_anchor_error(params.deck_rules.check(&deck), CustomError::CollectionNotApproved);
+    DeckRules::DEFAULT.check(&deck).unwrap();
+
+    // Stats stored out of the game's range never play
+    deck.stats[0].power = MAX_CARD_POWER + 1;
+    assert_anchor_error(DeckRules::DEFAULT.check(&deck), CustomError::InvalidCardAttributes);
+}
+
+impl Harness {
+    fn deck(&self, address: &Pubkey) -> DeckAccount {
+        let account = self.svm.get_account(address).unwrap();
+        accounts::deck_account(&account.data).unwrap()
+    }
+
+    /// Marks the deck as seated in the market's game with its first cards, as
+    /// a join does.
+    fn lock_deck(&mut self, address: &Pubkey) {
+        let mut account = self.svm.get_account(address).unwrap();
+        let mut deck = accounts::deck_account(&account.data).unwrap();
+        deck.locked_game = pda::game(&self.market).0;
+        deck.locked_pool = (0..deck.nfts.len().min(MAX_DECK_SIZE) as u16).collect();
+        account.data.clear();
+        deck.try_serialize(&mut account.data).unwrap();
+        self.svm.set_account(*address, account).unwrap();
+    }
+}
+
+#[test]
+fn deck_cards_can_be_changed_until_locked() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let cards: Vec<Pubkey> = (0..4)
+        .map(|_| set_asset(&mut h.svm, &owner, Some(CARD)))
+        .collect();
+    let spare = set_asset(&mut h.svm, &owner, Some(&[("power", "9")]));
+    let foreign = set_asset(&mut h.svm, &h.no.pubkey(), Some(CARD));
+    let address = pda::deck(&owner, 1).0;
+
+    h.send(instructions::create_deck(owner, 1, cards[..3].to_vec()), &player)
+        .unwrap();
+    // An existing deck is never replaced; its cards change one update at a time.
+    let result = h.send(
+        instructions::create_deck(owner, 1, vec![spare, cards[3], cards[0]]),
+        &player,
+    );
+    // The system program's AccountAlreadyInUse
+    assert_eq!(
+        result.expect_err("deck already exists").err,
+        TransactionError::InstructionError(0, InstructionError::Custom(0))
+    );
+    assert_eq!(h.deck(&address).nfts, cards[..3].to_vec());
+    let result = h.send(instructions::add_deck_cards(owner, address, vec![foreign]), &player);
+    assert_custom_error(result, CustomError::NotNftOwner);
+    let result = h.send(instructions::add_deck_cards(owner, address, vec![cards[0]]), &player);
+    assert_custom_error(result, CustomError::DuplicateCard);
+    let mut ix = instructions::add_deck_cards(owner, address, vec![cards[3]]);
+    ix.accounts.pop();
+    assert_custom_error(h.send(ix, &player), CustomError::MissingAccount);
+    h.send(instructions::add_deck_cards(owner, address, vec![cards[3]]), &player)
+        .unwrap();
+
+    let result = h.send(instructions::remove_deck_card(owner, address, 4), &player);
+    assert_custom_error(result, CustomError::InvalidCardIndex);
+    h.send(instructions::remove_deck_card(owner, address, 0), &player)
+        .unwrap();
+    let result = h.send(instructions::remove_deck_card(owner, address, 0), &player);
+    assert_custom_error(result, CustomError::InvalidDeckSize);
+    let result = h.send(
+        instructions::replace_deck_card(owner, address, 1, cards[3]),
+        &player,
+    );
+    assert_custom_error(result, CustomError::DuplicateCard);
+    h.send(instructions::replace_deck_card(owner, address, 1, spare), &player)
+        .unwrap();
+    let deck = h.deck(&address);
+    assert_eq!(deck.nfts, vec![cards[1], spare, cards[3]]);
+    assert_eq!(deck.stats[1].power, 9);
+
+    // Only the owner changes the deck.
+    let other = h.no.insecure_clone();
+    let result = h.send(instructions::close_deck(other.pubkey(), address), &other);
+    assert_custom_error(result, CustomError::InvalidDeckOwner);
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.player_yes = owner;
+    game.player_yes_deck = address;
+    h.set_game(&game);
+    h.lock_deck(&address);
+    let game_address = pda::game(&h.market).0;
+
+    for ix in [
+        instructions::add_deck_cards(owner, address, vec![cards[0]]),
+        instructions::remove_deck_card(owner, address, 0),
+        instructions::replace_deck_card(owner, address, 0, cards[0]),
+        instructions::close_deck(owner, address),
+        instructions::release_deck(owner, address, game_address, vec![]),
+    ] {
+        assert_custom_error(h.send(ix, &player), CustomError::DeckLocked);
+    }
+
+    // A finished game lets anyone release the deck.
+    game.result = ROUND_YES;
+    h.set_game(&game);
+    let cranker = h.creator.insecure_clone();
+    let cards = instructions::deck_cards(&h.deck(&address));
+    h.send(
+        instructions::release_deck(cranker.pubkey(), address, game_address, cards),
+        &cranker,
+    )
+    .unwrap();
+    assert_eq!(h.deck(&address).locked_game, Pubkey::default());
+
+    h.send(instructions::close_deck(owner, address), &player)
+        .unwrap();
+    assert_eq!(h.svm.get_account(&address).map_or(0, |account| account.lamports), 0);
+}
+
+#[test]
+fn decks_grow_past_one_page() {
+    let mut h = Harness::new(">=");
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let address = pda::deck(&owner, 1).0;
+    let cards: Vec<Pubkey> = (0..23)
+        .map(|_| set_asset(&mut h.svm, &owner, Some(CARD)))
+        .collect();
+
+    h.send(instructions::create_deck(owner, 1, cards[..3].to_vec()), &player)
+        .unwrap();
+    for batch in cards[3..].chunks(5) {
+        h.send(instructions::add_deck_cards(owner, address, batch.to_vec()), &player)
+            .unwrap();
+    }
+    let deck = h.deck(&address);
+    assert_eq!(deck.nfts, cards);
+    assert_eq!(
+        h.svm.get_account(&address).unwrap().data.len(),
+        DeckAccount::space(cards.len())
+    );
+
+    // A game plays a pool of up to one page of cards, picked from anywhere
+    // in the deck and in any order.
+    let pool = deck.select(&[22, 0, 21]).unwrap();
+    assert_eq!(pool.nfts, [cards[22], cards[0], cards[21]]);
+    assert!(pool.stats == [deck.stats[22], deck.stats[0], deck.stats[21]]);
+    assert_eq!(instructions::deck_cards(&pool).len(), 3);
+    let last: Vec<u16> = (3..23).collect();
+    assert_eq!(deck.select(&last).unwrap().nfts, cards[3..]);
+    assert_anchor_error(deck.select(&[23]), CustomError::InvalidDeckPool);
+    assert_anchor_error(deck.select(&[1, 0, 1]), CustomError::InvalidDeckPool);
+    let oversized: Vec<u16> = (0..=MAX_DECK_SIZE as u16).collect();
+    assert_anchor_error(deck.select(&oversized), CustomError::InvalidDeckPool);
+    // A shuffled game deals from a full page, so no player narrows the draw
+    // down to the hand they want.
+    assert_anchor_error(deck.check_deal_pool(&[22, 0, 21]), CustomError::DealPoolTooSmall);
+    deck.check_deal_pool(&last).unwrap();
+
+    // Removing a card moves the later ones down, so pools index the new order,
+    // and gives back its space.
+    h.send(instructions::remove_deck_card(owner, address, 21), &player)
+        .unwrap();
+    assert_eq!(h.deck(&address).select(&[20, 21]).unwrap().nfts, [cards[20], cards[22]]);
+    assert_eq!(
+        h.svm.get_account(&address).unwrap().data.len(),
+        DeckAccount::space(cards.len() - 1)
+    );
+
+    // Past `MAX_DECK_CARDS`, the deck is full.
+    let mut account = h.svm.get_account(&address).unwrap();
+    let mut full = h.deck(&address);
+    while full.nfts.len() < MAX_DECK_CARDS {
+        full.nfts.push(Pubkey::new_unique());
+        full.stats.push(full.stats[0]);
+        full.standards.push(AssetStandard::Core);
+    }
+    account.data.clear();
+    full.try_serialize(&mut account.data).unwrap();
+    account.lamports = h.svm.minimum_balance_for_rent_exemption(account.data.len());
+    h.svm.set_account(address, account).unwrap();
+    let result = h.send(instructions::add_deck_cards(owner, address, vec![cards[21]]), &player);
+    assert_custom_error(result, CustomError::DeckFull);
+
+    // Cards past the first 256 are still reachable by index.
+    h.send(instructions::remove_deck_card(owner, address, 300), &player)
+        .unwrap();
+    let deck = h.deck(&address);
+    assert_eq!(deck.nfts.len(), MAX_DECK_CARDS - 1);
+    assert!(!deck.nfts.contains(&full.nfts[300]));
+    assert_eq!(
+        h.svm.get_account(&address).unwrap().data.len(),
+        DeckAccount::space(MAX_DECK_CARDS - 1)
+    );
+}
+
+// Joins need the MXE, so the ownership check they run on the deck's asset
+// accounts is exercised directly.
+#[test]
+fn joining_deck_must_still_be_owned() {
+    let mut h = Harness::new(">=");
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let cards: Vec<Pubkey> = (0..3)
+        .map(|_| set_asset(&mut h.svm, &owner, Some(CARD)))
+        .collect();
+    h.send(instructions::create_deck(owner, 1, cards.clone()), &player)
+        .unwrap();
+    let deck = h.deck(&pda::deck(&owner, 1).0);
+
+    let mut assets: Vec<(Pubkey, Account)> = cards
+        .iter()
+        .map(|card| (*card, h.svm.get_account(card).unwrap()))
+        .collect();
+    let mut infos: Vec<AccountInfo> = assets
+        .iter_mut()
+        .map(|(key, account)| {
+            AccountInfo::new(
+                key,
+                false,
+                false,
+                &mut account.lamports,
+                &mut account.data,
+                &account.owner,
+                false,
+                0,
+            )
+        })
+        .collect();
+
+    check_deck_owned(&deck, &owner, &infos).unwrap();
+    assert_anchor_error(
+        check_deck_owned(&deck, &owner, &infos[..2]),
+        CustomError::MissingAccount,
+    );
+
+    // The player sells the last card once the deck is built; the asset owner
+    // follows the one byte key.
+    infos[2].try_borrow_mut_data().unwrap()[1..33].copy_from_slice(h.no.pubkey().as_ref());
+    assert_anchor_error(
+        check_deck_owned(&deck, &owner, &infos),
+        CustomError::NotNftOwner,
+    );
+    infos.swap(0, 1);
+    assert_anchor_error(
+        check_deck_owned(&deck, &owner, &infos),
+        CustomError::InvalidMint,
+    );
+}
+
+/// Writes a Token Metadata NFT held by `owner`, returning its mint and the
+/// token account holding it.
+fn set_legacy_nft(
+    svm: &mut LiteSVM,
+    owner: &Pubkey,
+    collection: Option<Pubkey>,
+) -> (Pubkey, Pubkey) {
+    let mint = Pubkey::new_unique();
+    let token_account = Pubkey::new_unique();
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner: *owner,
+        amount: 1,
+        delegate: COption::None,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    }
+    .pack_into_slice(&mut data);
+    svm.set_account(
+        token_account,
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    let mut data = Vec::new();
+    Metadata {
+        key: MetadataKey::MetadataV1,
+        update_authority: Pubkey::new_unique(),
+        mint,
+        name: "Card".to_string(),
+        symbol: String::new(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        primary_sale_happened: false,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: collection.map(|key| Collection {
+            verified: true,
+            key,
+        }),
+        uses: None,
+        collection_details: None,
+        programmable_config: None,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    svm.set_account(
+        Metadata::find_pda(&mint).0,
+        Account {
+            lamports: LAMPORTS_PER_SOL,
+            data,
+            owner: mpl_token_metadata::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    (mint, token_account)
+}
+
+#[test]
+fn decks_take_token_metadata_and_compressed_cards() {
+    let mut h = Harness::new(">=");
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let a = set_asset(&mut h.svm, &owner, Some(CARD));
+    let b = set_asset(&mut h.svm, &owner, Some(CARD));
+    let (mint, token_account) = set_legacy_nft(&mut h.svm, &owner, None);
+    let (other_mint, other_token_account) = set_legacy_nft(&mut h.svm, &h.no.pubkey(), None);
+
+    let result = h.send(
+        instructions::create_deck_with(
+            owner,
+            1,
+            vec![
+                DeckCard::core(a),
+                DeckCard::core(b),
+                DeckCard::token_metadata(other_mint, other_token_account),
+            ],
+        ),
+        &player,
+    );
+    assert_custom_error(result, CustomError::NotNftOwner);
+    let result = h.send(
+        instructions::create_deck_with(
+            owner,
+            1,
+            vec![
+                DeckCard::core(a),
+                DeckCard::core(b),
+                DeckCard::token_metadata(mint, other_token_account),
+            ],
+        ),
+        &player,
+    );
+    assert_custom_error(result, CustomError::InvalidTokenAccount);
+
+    // A leaf of an account that isn't a merkle tree can't prove anything.
+    let proof = CompressedProof {
+        root: [0; 32],
+        data_hash: [0; 32],
+        creator_hash: [0; 32],
+        delegate: owner,
+        nonce: 0,
+        index: 0,
+        proof_len: 0,
+    };
+    let fake_tree = set_asset(&mut h.svm, &owner, None);
+    let result = h.send(
+        instructions::create_deck_with(
+            owner,
+            1,
+            vec![
+                DeckCard::core(a),
+                DeckCard::core(b),
+                DeckCard::compressed(Pubkey::new_unique(), fake_tree, proof, &[[1; 32]]),
+            ],
+        ),
+        &player,
+    );
+    assert_custom_error(result, CustomError::InvalidCardProof);
+
+    h.send(
+        instructions::create_deck_with(
+            owner,
+            1,
+            vec![
+                DeckCard::core(a),
+                DeckCard::token_metadata(mint, token_account),
+                DeckCard::core(b),
+            ],
+        ),
+        &player,
+    )
+    .unwrap();
+    let deck = h.deck(&pda::deck(&owner, 1).0);
+    assert!(
+        deck.standards
+            == vec![
+                AssetStandard::Core,
+                AssetStandard::TokenMetadata,
+                AssetStandard::Core
+            ]
+    );
+    // Its stats live off chain, and it couldn't be frozen, so it sits in the
+    // deck but never plays, whatever accounts come with the join.
+    assert!(deck.stats[1] == CardStats::default());
+    assert_anchor_error(check_deck_owned(&deck, &owner, &[]), CustomError::CardNotPlayable);
+    // A pool of the deck's mpl-core cards plays, and only they are accounts.
+    let pool = deck.select(&[0, 2]).unwrap();
+    let cards = instructions::deck_cards(&pool);
+    assert_eq!(
+        cards.iter().map(|card| card.pubkey).collect::<Vec<_>>(),
+        vec![a, b]
+    );
+}
+
+// mpl-core itself, committed so every run tests the same build. The localnet
+// suite loads the same file through `[[test.genesis]]` in Anchor.toml.
+const MPL_CORE_SO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mpl_core.so");
+
+impl Harness {
+    fn load_mpl_core(&mut self) {
+        self.svm
+            .add_program_from_file(mpl_core::ID, MPL_CORE_SO)
+            .expect("mpl-core fixture missing, see MPL_CORE_SO");
+    }
+
+    /// Mints an mpl-core card through mpl-core itself, frozen under the
+    /// program's freeze authority as a join leaves it.
+    fn mint_frozen_card(&mut self, owner: &Keypair) -> Pubkey {
+        self.mint_frozen_card_in(owner, None)
+    }
+
+    /// Creates an mpl-core collection whose update authority is `owner`.
+    fn create_collection(&mut self, owner: &Keypair) -> Pubkey {
+        let collection = Keypair::new();
+        let ix = CreateCollectionV1Builder::new()
+            .collection(collection.pubkey())
+            .payer(owner.pubkey())
+            .name("Cards".to_string())
+            .uri(String::new())
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&owner.pubkey()),
+            &[owner, &collection],
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).unwrap();
+        collection.pubkey()
+    }
+
+    /// As `mint_frozen_card`, in `collection` when given; `owner` must be
+    /// the collection's update authority.
+    fn mint_frozen_card_in(&mut self, owner: &Keypair, collection: Option<Pubkey>) -> Pubkey {
+        let asset = Keypair::new();
+        let ix = CreateV1Builder::new()
+            .asset(asset.pubkey())
+            .collection(collection)
+            .payer(owner.pubkey())
+            .owner(Some(owner.pubkey()))
+            .data_state(DataState::AccountState)
+            .name("Card".to_string())
+            .uri(String::new())
+            .plugins(vec![
+                PluginAuthorityPair {
+                    plugin: Plugin::Attributes(Attributes {
+                        attribute_list: CARD
+                            .iter()
+                            .map(|(key, value)| Attribute {
+                                key: key.to_string(),
+                                value: value.to_string(),
+                            })
+                            .collect(),
+                    }),
+                    authority: None,
+                },
+                PluginAuthorityPair {
+                    plugin: Plugin::FreezeDelegate(FreezeDelegate { frozen: true }),
+                    authority: Some(PluginAuthority::Address {
+                        address: pda::freeze_authority().0,
+                    }),
+                },
+            ])
+            .instruction();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&owner.pubkey()),
+            &[owner, &asset],
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).unwrap();
+        asset.pubkey()
+    }
+}
+
+// Joins need the MXE, so the cards start out frozen as a join leaves them.
+#[test]
+fn releasing_a_deck_thaws_its_cards() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.load_mpl_core();
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let buyer = h.no.pubkey();
+    let cards: Vec<Pubkey> = (0..3).map(|_| h.mint_frozen_card(&player)).collect();
+    h.send(instructions::create_deck(owner, 1, cards.clone()), &player)
+        .unwrap();
+    let address = pda::deck(&owner, 1).0;
+    let game_address = pda::game(&h.market).0;
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.player_yes = owner;
+    game.player_yes_deck = address;
+    h.set_game(&game);
+    h.lock_deck(&address);
+
+    let transfer = move |card: Pubkey| {
+        TransferV1Builder::new()
+            .asset(card)
+            .payer(owner)
+            .new_owner(buyer)
+            .instruction()
+    };
+    // Cards can't be sold mid-game.
+    assert!(h.send(transfer(cards[0]), &player).is_err());
+
+    game.result = ROUND_YES;
+    h.set_game(&game);
+    let cranker = h.creator.insecure_clone();
+    let deck_cards = instructions::deck_cards(&h.deck(&address));
+
+    // The deck thaws as a whole or not at all.
+    let partial = instructions::release_deck(
+        cranker.pubkey(),
+        address,
+        game_address,
+        deck_cards[..2].to_vec(),
+    );
+    assert_custom_error(h.send(partial, &cranker), CustomError::MissingAccount);
+    assert!(h.send(transfer(cards[0]), &player).is_err());
+
+    h.send(
+        instructions::release_deck(cranker.pubkey(), address, game_address, deck_cards),
+        &cranker,
+    )
+    .unwrap();
+    for card in cards {
+        h.send(transfer(card), &player).unwrap();
+    }
+}
+
+// Escrowing an ante and freezing or thawing a deck all look a card's own
+// collection up among the card accounts, so a collection card thaws in a
+// deck whose cards don't share one.
+#[test]
+fn releasing_a_mixed_deck_thaws_its_collection_cards() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.load_mpl_core();
+    let player = h.yes.insecure_clone();
+    let owner = player.pubkey();
+    let collection = h.create_collection(&player);
+    let cards = vec![
+        h.mint_frozen_card_in(&player, Some(collection)),
+        h.mint_frozen_card(&player),
+        h.mint_frozen_card(&player),
+    ];
+    h.send(instructions::create_deck(owner, 1, cards.clone()), &player)
+        .unwrap();
+    let address = pda::deck(&owner, 1).0;
+    let deck = h.deck(&address);
+    assert_eq!(deck.collection, Pubkey::default());
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.player_yes = owner;
+    game.player_yes_deck = address;
+    game.result = ROUND_YES;
+    h.set_game(&game);
+    h.lock_deck(&address);
+    let game_address = pda::game(&h.market).0;
+    let cranker = h.creator.insecure_clone();
+
+    // mpl-core only thaws the collection card with its collection's account.
+    let mut cards_accounts = instructions::deck_cards(&deck);
+    let result = h.send(
+        instructions::release_deck(
+            cranker.pubkey(),
+            address,
+            game_address,
+            cards_accounts.clone(),
+        ),
+        &cranker,
+    );
+    assert_custom_error(result, CustomError::MissingAccount);
+
+    cards_accounts.extend(instructions::card_collections(&deck, [Some(collection), None, None]));
+    h.send(
+        instructions::release_deck(cranker.pubkey(), address, game_address, cards_accounts),
+        &cranker,
+    )
+    .unwrap();
+    for (card, collection) in cards.into_iter().zip([Some(collection), None, None]) {
+        let transfer = TransferV1Builder::new()
+            .asset(card)
+            .collection(collection)
+            .payer(owner)
+            .new_owner(h.no.pubkey())
+            .instruction();
+        h.send(transfer, &player).unwrap();
+    }
+}
+
+#[test]
+fn settled_antes_go_to_the_winner() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.load_mpl_core();
+    let game_address = pda::game(&h.market).0;
+    let yes = h.yes.pubkey();
+    let no = h.no.pubkey();
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.ante = true;
+    game.player_yes = yes;
+    game.player_no = no;
+    game.yes_ante = AnteStake {
+        asset: set_asset(&mut h.svm, &game_address, None),
+        owner: yes,
+    };
+    game.no_ante = AnteStake {
+        asset: set_asset(&mut h.svm, &game_address, None),
+        owner: no,
+    };
+    h.set_game(&game);
+    let cranker = h.creator.insecure_clone();
+
+    // Nothing is due while both players are still in the game.
+    let result = h.send(
+        instructions::settle_ante(cranker.pubkey(), h.market, vec![]),
+        &cranker,
+    );
+    assert_custom_error(result, CustomError::NoAnteToSettle);
+
+    game.result = ROUND_YES;
+    h.set_game(&game);
+    let mut antes = instructions::ante_accounts(&game, [None, None]);
+    assert_eq!(antes.len(), 4);
+    antes[3].pubkey = no;
+    let result = h.send(
+        instructions::settle_ante(cranker.pubkey(), h.market, antes),
+        &cranker,
+    );
+    assert_custom_error(result, CustomError::InvalidPayer);
+
+    let antes = instructions::ante_accounts(&game, [None, None]);
+    h.send(
+        instructions::settle_ante(cranker.pubkey(), h.market, antes),
+        &cranker,
+    )
+    .unwrap();
+    for asset in [game.yes_ante.asset, game.no_ante.asset] {
+        let data = h.svm.get_account(&asset).unwrap().data;
+        assert_eq!(BaseAssetV1::from_bytes(&data).unwrap().owner, yes);
+    }
+    let settled = h.game();
+    assert_eq!(settled.yes_ante.asset, Pubkey::default());
+    assert_eq!(settled.no_ante.asset, Pubkey::default());
+}
+
+#[test]
+fn reconcile_freezes_short_vault() {
+    let mut h = resolved_market();
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+
+    // Balanced vault: nothing changes even when asked to freeze.
+    let creator = h.creator.insecure_clone();
+    h.send(instructions::reconcile(creator.pubkey(), h.market, true), &creator)
+        .unwrap();
+    assert!(h.market_status() == Status::ResolvedYes);
+
+    let vault = pda::treasury_vault(&h.market).0;
+    let mut account = h.svm.get_account(&vault).unwrap();
+    account.lamports -= 1;
+    h.svm.set_account(vault, account).unwrap();
+
+    // Anyone reports a shortfall, only the owner freezes on it.
+    let cranker = h.no.insecure_clone();
+    h.send(instructions::reconcile(cranker.pubkey(), h.market, false), &cranker)
+        .unwrap();
+    assert!(h.market_status() == Status::ResolvedYes);
+    let result = h.send(instructions::reconcile(cranker.pubkey(), h.market, true), &cranker);
+    assert_custom_error(result, CustomError::NotMarketOwner);
+
+    h.send(instructions::reconcile(creator.pubkey(), h.market, true), &creator)
+        .unwrap();
+    assert!(h.market_status() == Status::Frozen);
+    assert!(h.treasury().status == Status::Frozen);
+    h.assert_treasury(0, 2 * BET / 100, 2 * BET / 100, 1, 1);
+
+    let result = h.send(instructions::claim_fees(h.market, creator.pubkey()), &creator);
+    assert_custom_error(result, CustomError::InvalidMarketStatus);
+
+    // The freeze holds until the vault is made whole again, and only the owner
+    // lifts it.
+    let result = h.send(instructions::unfreeze(creator.pubkey(), h.market), &creator);
+    assert_custom_error(result, CustomError::TreasuryShortfall);
+    let mut account = h.svm.get_account(&vault).unwrap();
+    account.lamports += 1;
+    h.svm.set_account(vault, account).unwrap();
+    let result = h.send(instructions::unfreeze(cranker.pubkey(), h.market), &cranker);
+    assert_custom_error(result, CustomError::NotMarketOwner);
+    h.send(instructions::unfreeze(creator.pubkey(), h.market), &creator)
+        .unwrap();
+    assert!(h.market_status() == Status::ResolvedYes);
+    assert!(h.treasury().status == Status::ResolvedYes);
+    let result = h.send(instructions::unfreeze(creator.pubkey(), h.market), &creator);
+    assert_custom_error(result, CustomError::InvalidMarketStatus);
+
+    h.send(instructions::claim_fees(h.market, creator.pubkey()), &creator)
+        .unwrap();
+}
+
+/// A game with no player joined yet, as `init_game` leaves it.
+fn pending_game(market: Pubkey, deadline: i64) -> GameAccount {
+    GameAccount {
+        market_account: market,
+        player_yes: Pubkey::default(),
+        player_yes_deck: Pubkey::default(),
+        player_no: Pubkey::default(),
+        player_no_deck: Pubkey::default(),
+        yes_cards1: [0; 32],
+        yes_cards2: [0; 32],
+        yes_cards3: [0; 32],
+        no_cards1: [0; 32],
+        no_cards2: [0; 32],
+        no_cards3: [0; 32],
+        yes_choice: [0; 32],
+        no_choice: [0; 32],
+        yes_stats: [[0; 32]; 6],
+        no_stats: [[0; 32]; 6],
+        yes_joined: [0; 32],
+        no_joined: [0; 32],
+        yes_rounds: [[0; 32]; 3],
+        no_rounds: [[0; 32]; 3],
+        yes_used: [0; 32],
+        no_used: [0; 32],
+        current_turn: 0,
+        result: ROUND_PENDING,
+        bump: pda::game(&market).1,
+        nonce: 0,
+        game_id: SEED,
+        yes_played: false,
+        no_played: false,
+        yes_wins: 0,
+        no_wins: 0,
+        deadline,
+        computation: PendingComputation {
+            offset: 0,
+            kind: ComputationKind::InitGame,
+            queued_slot: 0,
+            status: ComputationStatus::Finalized,
+            side: 0,
+        },
+        failed_computations: 0,
+        yes_view_key: [0; 32],
+        no_view_key: [0; 32],
+        round_winners: [ROUND_PENDING; 3],
+        deal_mode: DealMode::Chosen,
+        ante: false,
+        yes_ante: AnteStake::default(),
+        no_ante: AnteStake::default(),
+        yes_deck_pool: Vec::new(),
+        no_deck_pool: Vec::new(),
+    }
+}
+
+#[test]
+fn forfeit_awards_game_to_the_side_that_joined() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.player_yes = h.yes.pubkey();
+    h.set_game(&game);
+
+    let cranker = h.creator.insecure_clone();
+    let result = h.send(instructions::claim_forfeit(h.market), &cranker);
+    assert_custom_error(result, CustomError::DeadlineNotReached);
+
+    h.warp(101);
+    h.send(instructions::claim_forfeit(h.market), &cranker)
+        .unwrap();
+    assert_eq!(h.game().result, ROUND_YES);
+    assert!(h.market_status() == Status::ResolvedYes);
+
+    let result = h.send(instructions::claim_forfeit(h.market), &cranker);
+    assert_custom_error(result, CustomError::GameFinished);
+
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+}
+
+#[test]
+fn forfeit_cancels_game_nobody_joined() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+
+    let game = pending_game(h.market, h.now());
+    h.set_game(&game);
+    h.warp(1);
+
+    let cranker = h.creator.insecure_clone();
+    h.send(instructions::claim_forfeit(h.market), &cranker)
+        .unwrap();
+    assert_eq!(h.game().result, GAME_CANCELLED);
+    assert!(h.market_status() == Status::Cancelled);
+
+    for user in [h.yes.insecure_clone(), h.no.insecure_clone()] {
+        h.claim(&user).unwrap();
+    }
+    assert_eq!(h.vault_balance(), 0);
+}
+
+#[test]
+fn forfeit_settles_only_a_market_open_to_it() {
+    let forfeited = |h: &mut Harness| {
+        let mut game = pending_game(h.market, h.now());
+        game.player_yes = h.yes.pubkey();
+        h.set_game(&game);
+        h.warp(1);
+        let cranker = h.creator.insecure_clone();
+        h.send(instructions::claim_forfeit(h.market), &cranker)
+    };
+
+    // A game over before betting closed refunds the bets placed so far.
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.bet(true, BET).unwrap();
+    forfeited(&mut h).unwrap();
+    assert_eq!(h.game().result, ROUND_YES);
+    assert!(h.market_status() == Status::Cancelled);
+    let yes = h.yes.insecure_clone();
+    h.claim(&yes).unwrap();
+
+    // A market its owner cancelled is left as it is.
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    let creator = h.creator.insecure_clone();
+    h.send(instructions::cancel(h.market, creator.pubkey()), &creator)
+        .unwrap();
+    forfeited(&mut h).unwrap();
+    assert!(h.market_status() == Status::Cancelled);
+
+    // A frozen market takes no result until it is unfrozen.
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    h.bet(true, BET).unwrap();
+    h.bet(false, BET).unwrap();
+    let creator = h.creator.insecure_clone();
+    let vault = pda::treasury_vault(&h.market).0;
+    let mut account = h.svm.get_account(&vault).unwrap();
+    account.lamports -= 1;
+    h.svm.set_account(vault, account.clone()).unwrap();
+    h.send(instructions::reconcile(creator.pubkey(), h.market, true), &creator)
+        .unwrap();
+    assert_custom_error(forfeited(&mut h), CustomError::InvalidMarketStatus);
+
+    account.lamports += 1;
+    h.svm.set_account(vault, account).unwrap();
+    h.send(instructions::unfreeze(creator.pubkey(), h.market), &creator)
+        .unwrap();
+    h.send(instructions::claim_forfeit(h.market), &creator)
+        .unwrap();
+    assert!(h.market_status() == Status::ResolvedYes);
+}
+
+#[test]
+fn rollback_frees_side_of_stuck_join() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    let slot = h.svm.get_sysvar::<Clock>().slot;
+
+    let mut game = pending_game(h.market, h.now() + 100);
+    game.player_yes = h.yes.pubkey();
+    game.computation = PendingComputation {
+        offset: 7,
+        kind: ComputationKind::JoinGame,
+        queued_slot: slot,
+        status: ComputationStatus::Pending,
+        side: 0,
+    };
+    h.set_game(&game);
+
+    let cranker = h.creator.insecure_clone();
+    let result = h.send(instructions::rollback_computation(h.market), &cranker);
+    assert_custom_error(result, CustomError::ComputationNotRecoverable);
+
+    // Nothing else can run on the game while the join is pending.
+    h.warp(101);
+    let result = h.send(instructions::claim_forfeit(h.market), &cranker);
+    assert_custom_error(result, CustomError::ComputationPending);
+
+    h.svm.warp_to_slot(slot + GameAccount::COMPUTATION_TIMEOUT_SLOTS + 1);
+    h.send(instructions::rollback_computation(h.market), &cranker)
+        .unwrap();
+    let game = h.game();
+    assert_eq!(game.player_yes, Pubkey::default());
+    assert!(game.computation.status == ComputationStatus::Failed);
+    assert_eq!(game.failed_computations, 1);
+
+    let result = h.send(instructions::rollback_computation(h.market), &cranker);
+    assert_custom_error(result, CustomError::ComputationNotRecoverable);
+
+    // With the join undone, the expired game is cancelled.
+    h.send(instructions::claim_forfeit(h.market), &cranker)
+        .unwrap();
+    assert_eq!(h.game().result, GAME_CANCELLED);
+}
+
+// Only the Arcium program may run a callback, but the accounts it comes with
+// are checked before that: any executable stands in for Arcium here, and an
+// empty computation definition for the one the callback names.
+#[test]
+fn callback_for_another_game_is_rejected() {
+    let mut h = Harness::with_mode(">=", ResolutionMode::Game);
+    let so = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/knostra_arcium.so");
+    h.svm.add_program_from_file(ARCIUM_PROG_ID, so).unwrap();
+    let comp_def = pda::comp_def(comp_def_offset("reveal_game"));
+    let mut data = ComputationDefinitionAccount::DISCRIMINATOR.to_vec();
+    data.resize(1024, 0);
+    h.set_data(comp_def, ARCIUM_PROG_ID, data);
+
+    // This market's game and another market's, each with its history
+    let other_market = Pubkey::new_unique();
+    for market in [h.market, other_market] {
+        let game = pending_game(market, h.now() + 100);
+        h.set_game(&game);
+        let game_address = pda::game(&market).0;
+        let (history_address, bump) = pda::history(&game_address);
+        let history = GameHistory {
+            game_account: game_address,
+            market_account: market,
+            player_yes: Pubkey::default(),
+            player_yes_deck: Pubkey::default(),
+            player_no: Pubkey::default(),
+            player_no_deck: Pubkey::default(),
+            yes_cards: [0; 3],
+            no_cards: [0; 3],
+            yes_powers: [0; 3],
+            yes_elements: [0; 3],
+            no_powers: [0; 3],
+            no_elements: [0; 3],
+            yes_rounds: [0; 3],
+            no_rounds: [0; 3],
+            round_winners: [ROUND_PENDING; 3],
+            result: ROUND_PENDING,
+            revealed: false,
+            revealed_at: 0,
+            bump,
+            yes_deck_pool: Vec::new(),
+            no_deck_pool: Vec::new(),
+        };
+        let mut data = Vec::new();
+        history.try_serialize(&mut data).unwrap();
+        h.set_data(history_address, PROGRAM_ID, data);
+    }
+    let market = h.market;
+    let callback = move |game: Pubkey| {
+        let mut data = knostra_arcium::instruction::RevealGameCallback::DISCRIMINATOR.to_vec();
+        data.resize(64, 0);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: knostra_arcium::accounts::RevealGameCallback {
+                arcium_program: ARCIUM_PROG_ID,
+                comp_def_account: comp_def,
+                instructions_sysvar: sysvar::instructions::ID,
+                market_account: market,
+                game_account: game,
+                game_history: pda::history(&game).0,
+            }
+            .to_account_metas(None),
+            data,
+        }
+    };
+    let seeds_error = TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(ErrorCode::ConstraintSeeds.into()),
+    );
+
+    // Sent with the market's own game, the callback gets past its accounts...
+    let cranker = h.creator.insecure_clone();
+    let own = callback(pda::game(&market).0);
+    if let Err(failed) = h.send(own, &cranker) {
+        assert_ne!(failed.err, seeds_error);
+    }
+    // ...but another market's game doesn't derive from the market queued with it.
+    let other = callback(pda::game(&other_market).0);
+    assert_eq!(h.send(other, &cranker).expect_err("game mismatch").err, seeds_error);
+
+    // The output itself must also echo the offset this game queued.
+    let queued = |market: Pubkey, offset: u64| {
+        let mut game = pending_game(market, 0);
+        game.computation = PendingComputation {
+            offset,
+            kind: ComputationKind::JoinGame,
+            queued_slot: 0,
+            status: ComputationStatus::Pending,
+            side: 0,
+        };
+        game
+    };
+    let game = queued(Pubkey::new_unique(), 7);
+    let other = queued(Pubkey::new_unique(), 9);
+
+    game.verify_callback(7).unwrap();
+    assert_anchor_error(other.verify_callback(7), CustomError::CallbackMismatch);
+}
+
+// A join runs these checks before queueing its computation, but only after
+// Anchor has loaded the Arcium accounts, so they are exercised directly.
+#[test]
+fn seats_take_the_players_own_bet_and_deck() {
+    let player = Pubkey::new_unique();
+    let game = pending_game(Pubkey::new_unique(), 0);
+    let bet = BetAccount {
+        market_account: game.market_account,
+        user: player,
+        bump: 0,
+        bet_amount: BET,
+        choice: true,
+        claimed: false,
+    };
+    let deck = DeckAccount {
+        owner: player,
+        nfts: Vec::new(),
+        bump: 0,
+        stats: Vec::new(),
+        locked_game: Pubkey::default(),
+        collection: Pubkey::default(),
+        standards: Vec::new(),
+        locked_pool: Vec::new(),
+    };
+    game.check_seat(&bet, &deck, &player).unwrap();
+
+    let stranger = Pubkey::new_unique();
+    assert_anchor_error(game.check_seat(&bet, &deck, &stranger), CustomError::InvalidPayer);
+    // A bet on another market can't seat its owner in this game.
+    let other_market = BetAccount {
+        market_account: Pubkey::new_unique(),
+        ..bet.clone()
+    };
+    assert_anchor_error(
+        game.check_seat(&other_market, &deck, &player),
+        CustomError::GameMarketMismatch,
+    );
+    // Nor can someone else's deck.
+    let borrowed = DeckAccount {
+        owner: stranger,
+        ..deck
+    };
+    assert_anchor_error(
+        game.check_seat(&bet, &borrowed, &player),
+        CustomError::InvalidDeckOwner,
+    );
+}
+
+/// Wire size of a transaction with one signature.
+fn signed_size(message: Vec<u8>) -> usize {
+    1 + 64 + message.len()
+}
+
+#[test]
+fn full_pool_join_fits_a_lookup_table_transaction() {
+    let player = Keypair::new();
+    let owner = player.pubkey();
+    let nfts: Vec<Pubkey> = (0..MAX_DECK_SIZE).map(|_| Pubkey::new_unique()).collect();
+    let deck = DeckAccount {
+        owner,
+        bump: 0,
+        stats: vec![CardStats::default(); nfts.len()],
+        standards: vec![AssetStandard::Core; nfts.len()],
+        nfts,
+        locked_game: Pubkey::default(),
+        collection: Pubkey::new_unique(),
+        locked_pool: Vec::new(),
+    };
+    let ix = instructions::join_game(
+        owner,
+        Pubkey::new_unique(),
+        0,
+        Pubkey::new_unique(),
+        pda::deck(&owner, 1).0,
+        instructions::deck_cards(&deck),
+        [1; 32],
+        [2; 32],
+        [3; 32],
+        [4; 32],
+        0,
+        Some(0),
+        (0..MAX_DECK_SIZE as u16).collect(),
+    );
+
+    // Every card is an account, more than a legacy transaction holds.
+    let legacy = Transaction::new_signed_with_payer(
+        &[ix.clone()],
+        Some(&owner),
+        &[&player],
+        Hash::default(),
+    );
+    assert!(signed_size(legacy.message.serialize()) > PACKET_DATA_SIZE);
+
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: transaction::lookup_addresses(&owner, std::slice::from_ref(&ix)),
+    };
+    let message = transaction::message(
+        &owner,
+        &[ix],
+        JOIN_COMPUTE_UNITS,
+        &[table],
+        Hash::default(),
+    )
+    .unwrap();
+    let tx = VersionedTransaction::try_new(message, &[&player]).unwrap();
+    assert!(signed_size(tx.message.serialize()) <= PACKET_DATA_SIZE);
+}
@@ -1,13 +1,16 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import {
+  ComputeBudgetProgram,
   Keypair,
   LAMPORTS_PER_SOL,
   PublicKey,
   SystemProgram,
+  Transaction,
+  TransactionInstruction,
 } from "@solana/web3.js";
 import { KnostraArcium } from "../target/types/knostra_arcium";
-import { randomBytes } from "crypto";
+import { createHash, randomBytes } from "crypto";
 import {
   awaitComputationFinalization,
   getArciumEnv,
@@ -105,7 +108,10 @@ describe("KnostraArcium", () => {
     const ciphertext = cipher.encrypt(plaintext, nonce);
 
     const sumEventPromise = awaitEvent("sumEvent");
-    const computationOffset = new anchor.BN(randomBytes(8), "hex");
+    const computationOffset = addTogetherOffset(
+      provider.wallet.publicKey,
+      nonce
+    );
 
     const queueSig = await program.methods
       .addTogether(
@@ -238,6 +244,8 @@ describe("KnostraArcium", () => {
       targetValue: new anchor.BN(120000),
       requiredBetAmount: new anchor.BN(1_000_000_000), // 1 SOL
       maxPlayerCount: new anchor.BN(1),
+      resolutionMode: { value: {} },
+      deckRules: { minSize: 3, maxSize: 20, collection: PublicKey.default },
     };
 
     // Call the create instruction
@@ -524,6 +532,8 @@ describe("KnostraArcium", () => {
       targetValue: new anchor.BN(120000),
       requiredBetAmount: new anchor.BN(1_000_000_000), // 1 SOL
       maxPlayerCount: new anchor.BN(1),
+      resolutionMode: { value: {} },
+      deckRules: { minSize: 3, maxSize: 20, collection: PublicKey.default },
     };
 
     // Call the create instruction
@@ -615,6 +625,55 @@ describe("KnostraArcium", () => {
     );
   });
 
+  // Game-mode market the game below is set up on, by its creator
+  const seed3 = new anchor.BN(3);
+  const [marketAccount3, marketBump3] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("market"),
+      marketCreator.publicKey.toBuffer(),
+      seed3.toArrayLike(Buffer, "le", 8),
+    ],
+    program.programId
+  );
+  const [treasuryAccount3, treasuryBump3] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury"), marketAccount3.toBuffer()],
+    program.programId
+  );
+  const [treasuryVault3] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury_vault"), marketAccount3.toBuffer()],
+    program.programId
+  );
+
+  it("Create game market", async () => {
+    const createMarketParams = {
+      name: "Card game market",
+      description: "Will YES win the card game?",
+      token: "SOL",
+      marketStart: new anchor.BN(Math.floor(Date.now() / 1000)),
+      marketEnd: new anchor.BN(Math.floor(Date.now() / 1000) + 3600), // +1 hour
+      relationalValue: ">=",
+      targetValue: new anchor.BN(0),
+      requiredBetAmount: new anchor.BN(1_000_000_000), // 1 SOL
+      maxPlayerCount: new anchor.BN(1),
+      resolutionMode: { game: {} },
+      deckRules: { minSize: 3, maxSize: 20, collection: PublicKey.default },
+    };
+
+    await program.methods
+      .create(seed3, createMarketParams, marketBump3, treasuryBump3)
+      .accountsPartial({
+        marketAccount: marketAccount3,
+        treasuryAccount: treasuryAccount3,
+        systemProgram: SystemProgram.programId,
+        treasuryVault: treasuryVault3,
+        user: marketCreator.publicKey,
+      })
+      .signers([marketCreator])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
   it("Initialize init game computation definition", async () => {
     const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
 
@@ -633,23 +692,35 @@ describe("KnostraArcium", () => {
     console.log("MXE x25519 pubkey is", mxePublicKey);
 
     console.log("initialize a new game");
-    const gameId = 1;
     const nonce = randomBytes(16);
 
-    const initComputationOffset = new anchor.BN(randomBytes(8), "hex");
+    // Only the market's creator sets up its game
+    const [gameAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), marketAccount3.toBuffer()],
+      program.programId
+    );
+    const initComputationOffset = gameComputationOffset(
+      gameAccount,
+      ComputationKind.InitGame,
+      0,
+      0,
+      0
+    );
 
     const initGameTx = await program.methods
       .initGame(
         initComputationOffset,
-        new anchor.BN(gameId),
-        new anchor.BN(deserializeLE(nonce).toString())
+        new anchor.BN(deserializeLE(nonce).toString()),
+        { chosen: {} },
+        false
       )
       .accountsPartial({
         computationAccount: getComputationAccAddress(
           program.programId,
           initComputationOffset
         ),
-        payer: owner.publicKey,
+        payer: marketCreator.publicKey,
+        marketAccount: marketAccount3,
         mxeAccount: getMXEAccAddress(program.programId),
         mempoolAccount: getMempoolAccAddress(program.programId),
         executingPool: getExecutingPoolAccAddress(program.programId),
@@ -659,7 +730,7 @@ describe("KnostraArcium", () => {
         ),
         clusterAccount: arciumEnv.arciumClusterPubkey,
       })
-      .signers([owner])
+      .signers([marketCreator])
       .rpc({ skipPreflight: true, commitment: "confirmed" })
       .then(confirm)
       .then(log);
@@ -676,6 +747,708 @@ describe("KnostraArcium", () => {
     console.log("Init game finalize signature:", initGameFinalizeSig);
   });
 
+  const [bettorYesAccount3, bettorYesBump3] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("bet"),
+      marketAccount3.toBuffer(),
+      bettorYes.publicKey.toBuffer(),
+    ],
+    program.programId
+  );
+
+  it("Place bet YES on the game market", async () => {
+    await program.methods
+      .bet(new anchor.BN(1_000_000_000), true, bettorYesBump3) // 1 SOL
+      .accountsPartial({
+        marketAccount: marketAccount3,
+        treasuryAccount: treasuryAccount3,
+        betAccount: bettorYesAccount3,
+        user: bettorYes.publicKey,
+        treasuryVault: treasuryVault3,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettorYes])
+      .rpc()
+      .then(confirm)
+      .then(log);
+  });
+
+  // Key the YES player registers on joining, to read their view of the game
+  const yesViewKey = x25519.utils.randomSecretKey();
+
+  it("Join game freezes the deck's cards", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    await initJoinGameCompDef(program, owner, false, false);
+
+    // A deck of three mpl-core cards, minted straight through mpl-core
+    const cards = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const card of cards) {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          createCoreCardIx(card.publicKey, bettorYes.publicKey)
+        ),
+        [bettorYes, card]
+      );
+    }
+    const deckSeed = new anchor.BN(1);
+    const [deckAccount, deckBump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deck"),
+        bettorYes.publicKey.toBuffer(),
+        deckSeed.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const cardAccounts = cards.map((card) => ({
+      pubkey: card.publicKey,
+      isSigner: false,
+      isWritable: true,
+    }));
+    await program.methods
+      .createDeck(
+        deckSeed,
+        cards.map((card) => card.publicKey),
+        cards.map(() => ({ core: {} })),
+        [],
+        deckBump
+      )
+      .accountsPartial({
+        deckAccount,
+        owner: bettorYes.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(cardAccounts)
+      .signers([bettorYes])
+      .rpc({ commitment: "confirmed" })
+      .then(log);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    const publicKey = x25519.getPublicKey(yesViewKey);
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(yesViewKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const hand = cipher.encrypt([BigInt(0), BigInt(1), BigInt(2)], nonce);
+
+    const [gameAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), marketAccount3.toBuffer()],
+      program.programId
+    );
+    const joinComputationOffset = gameComputationOffset(
+      gameAccount,
+      ComputationKind.JoinGame,
+      0,
+      0,
+      0
+    );
+    await program.methods
+      .joinGame(
+        joinComputationOffset,
+        Array.from(hand[0]),
+        Array.from(hand[1]),
+        Array.from(hand[2]),
+        Array.from(publicKey),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        null,
+        cards.map((_, index) => index)
+      )
+      .accountsPartial({
+        payer: bettorYes.publicKey,
+        computationAccount: getComputationAccAddress(
+          program.programId,
+          joinComputationOffset
+        ),
+        clusterAccount: arciumEnv.arciumClusterPubkey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(program.programId),
+        executingPool: getExecutingPoolAccAddress(program.programId),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("join_game")).readUInt32LE()
+        ),
+        gameAccount,
+        deckAccount,
+        betAccount: bettorYesAccount3,
+        marketAccount: marketAccount3,
+        freezeAuthority: freezeAuthority(program.programId),
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      })
+      .remainingAccounts(cardAccounts)
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+      ])
+      .signers([bettorYes])
+      .rpc({ skipPreflight: true, commitment: "confirmed" })
+      .then(log);
+
+    // Every card is held frozen under the program's freeze authority
+    for (const card of cards) {
+      const asset = await provider.connection.getAccountInfo(
+        card.publicKey,
+        "confirmed"
+      );
+      const freeze = coreFreezeDelegate(asset.data);
+      expect(freeze).to.not.equal(null);
+      expect(freeze.frozen).to.equal(true);
+      expect(freeze.authority?.toBase58()).to.equal(
+        freezeAuthority(program.programId).toBase58()
+      );
+    }
+
+    // ...so it can't change hands mid-game
+    let transferred = true;
+    try {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          transferCoreCardIx(
+            cards[0].publicKey,
+            bettorYes.publicKey,
+            bettorNo.publicKey
+          )
+        ),
+        [bettorYes]
+      );
+    } catch (error) {
+      transferred = false;
+    }
+    expect(transferred).to.equal(false);
+
+    // The deck stays locked to the game with the pool it joined with
+    const deck = await program.account.deckAccount.fetch(
+      deckAccount,
+      "confirmed"
+    );
+    expect(deck.lockedGame.toBase58()).to.equal(gameAccount.toBase58());
+    expect(deck.lockedPool).to.deep.equal([0, 1, 2]);
+
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      joinComputationOffset,
+      program.programId,
+      "confirmed"
+    );
+  });
+
+  const [bettorNoAccount3, bettorNoBump3] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("bet"),
+      marketAccount3.toBuffer(),
+      bettorNo.publicKey.toBuffer(),
+    ],
+    program.programId
+  );
+
+  it("Play turn remaps a replayed slot and decides each round", async () => {
+    const owner = readKpJson(`${os.homedir()}/.config/solana/id.json`);
+    await initPlayTurnCompDef(program, owner, false, false);
+
+    // The NO bet closes betting, so the game can be played
+    await program.methods
+      .bet(new anchor.BN(1_000_000_000), false, bettorNoBump3)
+      .accountsPartial({
+        marketAccount: marketAccount3,
+        treasuryAccount: treasuryAccount3,
+        betAccount: bettorNoAccount3,
+        user: bettorNo.publicKey,
+        treasuryVault: treasuryVault3,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettorNo])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    // NO joins with a deck of its own three cards
+    const cards = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+    for (const card of cards) {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          createCoreCardIx(card.publicKey, bettorNo.publicKey)
+        ),
+        [bettorNo, card]
+      );
+    }
+    const deckSeed = new anchor.BN(1);
+    const [deckAccount, deckBump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deck"),
+        bettorNo.publicKey.toBuffer(),
+        deckSeed.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const cardAccounts = cards.map((card) => ({
+      pubkey: card.publicKey,
+      isSigner: false,
+      isWritable: true,
+    }));
+    await program.methods
+      .createDeck(
+        deckSeed,
+        cards.map((card) => card.publicKey),
+        cards.map(() => ({ core: {} })),
+        [],
+        deckBump
+      )
+      .accountsPartial({
+        deckAccount,
+        owner: bettorNo.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(cardAccounts)
+      .signers([bettorNo])
+      .rpc({ commitment: "confirmed" })
+      .then(log);
+
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    const noViewKey = x25519.utils.randomSecretKey();
+    const players = [
+      { bettor: bettorYes, viewKey: yesViewKey },
+      { bettor: bettorNo, viewKey: noViewKey },
+    ];
+    const ciphers = players.map(
+      ({ viewKey }) =>
+        new RescueCipher(x25519.getSharedSecret(viewKey, mxePublicKey))
+    );
+
+    const [gameAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("game"), marketAccount3.toBuffer()],
+      program.programId
+    );
+    const joinNonce = randomBytes(16);
+    const hand = ciphers[1].encrypt(
+      [BigInt(0), BigInt(1), BigInt(2)],
+      joinNonce
+    );
+    const joinComputationOffset = gameComputationOffset(
+      gameAccount,
+      ComputationKind.JoinGame,
+      0,
+      1,
+      0
+    );
+    await program.methods
+      .joinGame(
+        joinComputationOffset,
+        Array.from(hand[0]),
+        Array.from(hand[1]),
+        Array.from(hand[2]),
+        Array.from(x25519.getPublicKey(noViewKey)),
+        new anchor.BN(deserializeLE(joinNonce).toString()),
+        null,
+        cards.map((_, index) => index)
+      )
+      .accountsPartial({
+        payer: bettorNo.publicKey,
+        computationAccount: getComputationAccAddress(
+          program.programId,
+          joinComputationOffset
+        ),
+        clusterAccount: arciumEnv.arciumClusterPubkey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(program.programId),
+        executingPool: getExecutingPoolAccAddress(program.programId),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("join_game")).readUInt32LE()
+        ),
+        gameAccount,
+        deckAccount,
+        betAccount: bettorNoAccount3,
+        marketAccount: marketAccount3,
+        freezeAuthority: freezeAuthority(program.programId),
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      })
+      .remainingAccounts(cardAccounts)
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+      ])
+      .signers([bettorNo])
+      .rpc({ skipPreflight: true, commitment: "confirmed" })
+      .then(log);
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      joinComputationOffset,
+      program.programId,
+      "confirmed"
+    );
+    let game = await program.account.gameAccount.fetch(
+      gameAccount,
+      "confirmed"
+    );
+    expect(game.playerYes.toBase58()).to.equal(bettorYes.publicKey.toBase58());
+    expect(game.playerNo.toBase58()).to.equal(bettorNo.publicKey.toBase58());
+
+    // Plays card index `choice` for `side` and returns the YES view after it
+    const play = async (side: number, choice: number) => {
+      const { bettor, viewKey } = players[side];
+      const game = await program.account.gameAccount.fetch(
+        gameAccount,
+        "confirmed"
+      );
+      const computationOffset = gameComputationOffset(
+        gameAccount,
+        ComputationKind.PlayTurn,
+        game.currentTurn,
+        side,
+        game.failedComputations
+      );
+      const nonce = randomBytes(16);
+      const [cardChoice] = ciphers[side].encrypt([BigInt(choice)], nonce);
+      const viewUpdated = awaitEvent("playerViewUpdated");
+      await program.methods
+        .playTurn(
+          computationOffset,
+          Array.from(cardChoice),
+          Array.from(x25519.getPublicKey(viewKey)),
+          new anchor.BN(deserializeLE(nonce).toString())
+        )
+        .accountsPartial({
+          payer: bettor.publicKey,
+          computationAccount: getComputationAccAddress(
+            program.programId,
+            computationOffset
+          ),
+          clusterAccount: arciumEnv.arciumClusterPubkey,
+          mxeAccount: getMXEAccAddress(program.programId),
+          mempoolAccount: getMempoolAccAddress(program.programId),
+          executingPool: getExecutingPoolAccAddress(program.programId),
+          compDefAccount: getCompDefAccAddress(
+            program.programId,
+            Buffer.from(getCompDefAccOffset("play_turn")).readUInt32LE()
+          ),
+          gameAccount,
+          marketAccount: marketAccount3,
+          treasuryAccount: treasuryAccount3,
+        })
+        .signers([bettor])
+        .rpc({ skipPreflight: true, commitment: "confirmed" })
+        .then(log);
+      await awaitComputationFinalization(
+        provider as anchor.AnchorProvider,
+        computationOffset,
+        program.programId,
+        "confirmed"
+      );
+      // The YES view is emitted first
+      const event = await viewUpdated;
+      return ciphers[0].decrypt(
+        event.view,
+        event.nonce.toArrayLike(Buffer, "le", 16)
+      );
+    };
+
+    // Round 1: both play their first card, and the round gets a winner
+    await play(0, 0);
+    await play(1, 0);
+    game = await program.account.gameAccount.fetch(gameAccount, "confirmed");
+    expect(game.currentTurn).to.equal(1);
+    expect(game.roundWinners[0]).to.not.equal(0);
+
+    // Round 2: YES replays its first card, and plays the second one instead.
+    // `choice` is the 10th field of the view, 1-based, while the round is open
+    const view = await play(0, 0);
+    expect(view[9]).to.equal(BigInt(2));
+    await play(1, 1);
+    game = await program.account.gameAccount.fetch(gameAccount, "confirmed");
+    expect(game.currentTurn).to.equal(2);
+    expect(game.roundWinners[1]).to.not.equal(0);
+  });
+
+  // Second game market, for joins the MXE refuses
+  const seed4 = new anchor.BN(4);
+  const [marketAccount4, marketBump4] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("market"),
+      marketCreator.publicKey.toBuffer(),
+      seed4.toArrayLike(Buffer, "le", 8),
+    ],
+    program.programId
+  );
+  const [treasuryAccount4, treasuryBump4] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury"), marketAccount4.toBuffer()],
+    program.programId
+  );
+  const [treasuryVault4] = PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury_vault"), marketAccount4.toBuffer()],
+    program.programId
+  );
+  const [gameAccount4] = PublicKey.findProgramAddressSync(
+    [Buffer.from("game"), marketAccount4.toBuffer()],
+    program.programId
+  );
+  const [bettorYesAccount4, bettorYesBump4] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("bet"),
+      marketAccount4.toBuffer(),
+      bettorYes.publicKey.toBuffer(),
+    ],
+    program.programId
+  );
+  const cards4 = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+  const cardAccounts4 = cards4.map((card) => ({
+    pubkey: card.publicKey,
+    isSigner: false,
+    isWritable: true,
+  }));
+  const deckSeed4 = new anchor.BN(4);
+  const [deckAccount4, deckBump4] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("deck"),
+      bettorYes.publicKey.toBuffer(),
+      deckSeed4.toArrayLike(Buffer, "le", 8),
+    ],
+    program.programId
+  );
+
+  // Joins the YES side of the second game with `hand`, indices into its deck,
+  // and returns the MXE's verdict once the join is finalized
+  const joinGame4 = async (hand: number[]) => {
+    const mxePublicKey = await getMXEPublicKeyWithRetry(
+      provider as anchor.AnchorProvider,
+      program.programId
+    );
+    const cipher = new RescueCipher(
+      x25519.getSharedSecret(yesViewKey, mxePublicKey)
+    );
+    const nonce = randomBytes(16);
+    const encrypted = cipher.encrypt(
+      hand.map((index) => BigInt(index)),
+      nonce
+    );
+    const game = await program.account.gameAccount.fetch(
+      gameAccount4,
+      "confirmed"
+    );
+    const computationOffset = gameComputationOffset(
+      gameAccount4,
+      ComputationKind.JoinGame,
+      0,
+      0,
+      game.failedComputations
+    );
+    const joined = awaitEvent("gameJoined");
+    await program.methods
+      .joinGame(
+        computationOffset,
+        Array.from(encrypted[0]),
+        Array.from(encrypted[1]),
+        Array.from(encrypted[2]),
+        Array.from(x25519.getPublicKey(yesViewKey)),
+        new anchor.BN(deserializeLE(nonce).toString()),
+        null,
+        cards4.map((_, index) => index)
+      )
+      .accountsPartial({
+        payer: bettorYes.publicKey,
+        computationAccount: getComputationAccAddress(
+          program.programId,
+          computationOffset
+        ),
+        clusterAccount: arciumEnv.arciumClusterPubkey,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(program.programId),
+        executingPool: getExecutingPoolAccAddress(program.programId),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("join_game")).readUInt32LE()
+        ),
+        gameAccount: gameAccount4,
+        deckAccount: deckAccount4,
+        betAccount: bettorYesAccount4,
+        marketAccount: marketAccount4,
+        freezeAuthority: freezeAuthority(program.programId),
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      })
+      .remainingAccounts(cardAccounts4)
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 1_400_000 }),
+      ])
+      .signers([bettorYes])
+      .rpc({ skipPreflight: true, commitment: "confirmed" })
+      .then(log);
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      computationOffset,
+      program.programId,
+      "confirmed"
+    );
+    return joined;
+  };
+
+  const releaseDeck4 = async () => {
+    await program.methods
+      .releaseDeck()
+      .accountsPartial({
+        deckAccount: deckAccount4,
+        gameAccount: gameAccount4,
+        payer: bettorYes.publicKey,
+        freezeAuthority: freezeAuthority(program.programId),
+        mplCoreProgram: MPL_CORE_PROGRAM_ID,
+      })
+      .remainingAccounts(cardAccounts4)
+      .signers([bettorYes])
+      .rpc({ commitment: "confirmed" })
+      .then(log);
+  };
+
+  it("Join game refuses duplicate or out-of-deck cards", async () => {
+    await program.methods
+      .create(
+        seed4,
+        {
+          name: "Refused joins market",
+          description: "Will YES win the card game?",
+          token: "SOL",
+          marketStart: new anchor.BN(Math.floor(Date.now() / 1000)),
+          marketEnd: new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+          relationalValue: ">=",
+          targetValue: new anchor.BN(0),
+          requiredBetAmount: new anchor.BN(1_000_000_000), // 1 SOL
+          maxPlayerCount: new anchor.BN(1),
+          resolutionMode: { game: {} },
+          deckRules: { minSize: 3, maxSize: 20, collection: PublicKey.default },
+        },
+        marketBump4,
+        treasuryBump4
+      )
+      .accountsPartial({
+        marketAccount: marketAccount4,
+        treasuryAccount: treasuryAccount4,
+        systemProgram: SystemProgram.programId,
+        treasuryVault: treasuryVault4,
+        user: marketCreator.publicKey,
+      })
+      .signers([marketCreator])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    const initComputationOffset = gameComputationOffset(
+      gameAccount4,
+      ComputationKind.InitGame,
+      0,
+      0,
+      0
+    );
+    await program.methods
+      .initGame(
+        initComputationOffset,
+        new anchor.BN(deserializeLE(randomBytes(16)).toString()),
+        { chosen: {} },
+        false
+      )
+      .accountsPartial({
+        computationAccount: getComputationAccAddress(
+          program.programId,
+          initComputationOffset
+        ),
+        payer: marketCreator.publicKey,
+        marketAccount: marketAccount4,
+        mxeAccount: getMXEAccAddress(program.programId),
+        mempoolAccount: getMempoolAccAddress(program.programId),
+        executingPool: getExecutingPoolAccAddress(program.programId),
+        compDefAccount: getCompDefAccAddress(
+          program.programId,
+          Buffer.from(getCompDefAccOffset("init_game")).readUInt32LE()
+        ),
+        clusterAccount: arciumEnv.arciumClusterPubkey,
+      })
+      .signers([marketCreator])
+      .rpc({ skipPreflight: true, commitment: "confirmed" })
+      .then(log);
+    await awaitComputationFinalization(
+      provider as anchor.AnchorProvider,
+      initComputationOffset,
+      program.programId,
+      "confirmed"
+    );
+
+    await program.methods
+      .bet(new anchor.BN(1_000_000_000), true, bettorYesBump4)
+      .accountsPartial({
+        marketAccount: marketAccount4,
+        treasuryAccount: treasuryAccount4,
+        betAccount: bettorYesAccount4,
+        user: bettorYes.publicKey,
+        treasuryVault: treasuryVault4,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([bettorYes])
+      .rpc()
+      .then(confirm)
+      .then(log);
+
+    for (const card of cards4) {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          createCoreCardIx(card.publicKey, bettorYes.publicKey)
+        ),
+        [bettorYes, card]
+      );
+    }
+    await program.methods
+      .createDeck(
+        deckSeed4,
+        cards4.map((card) => card.publicKey),
+        cards4.map(() => ({ core: {} })),
+        [],
+        deckBump4
+      )
+      .accountsPartial({
+        deckAccount: deckAccount4,
+        owner: bettorYes.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(cardAccounts4)
+      .signers([bettorYes])
+      .rpc({ commitment: "confirmed" })
+      .then(log);
+
+    // The same card twice
+    let joined = await joinGame4([0, 0, 1]);
+    expect(joined.accepted).to.equal(false);
+
+    // A refused deck is released before it joins again
+    await releaseDeck4();
+
+    // An index past the end of the three-card deck
+    joined = await joinGame4([0, 1, 3]);
+    expect(joined.accepted).to.equal(false);
+  });
+
+  it("A refused join frees its side for a rejoin", async () => {
+    // The refused join above left the side empty, its deck still locked
+    let game = await program.account.gameAccount.fetch(
+      gameAccount4,
+      "confirmed"
+    );
+    expect(game.playerYes.toBase58()).to.equal(PublicKey.default.toBase58());
+    expect(game.failedComputations).to.equal(2);
+    let deck = await program.account.deckAccount.fetch(
+      deckAccount4,
+      "confirmed"
+    );
+    expect(deck.lockedGame.toBase58()).to.equal(gameAccount4.toBase58());
+
+    // Once released, the deck joins again with a valid hand
+    await releaseDeck4();
+    const joined = await joinGame4([0, 1, 2]);
+    expect(joined.accepted).to.equal(true);
+
+    game = await program.account.gameAccount.fetch(gameAccount4, "confirmed");
+    expect(game.playerYes.toBase58()).to.equal(bettorYes.publicKey.toBase58());
+    expect(game.playerYesDeck.toBase58()).to.equal(deckAccount4.toBase58());
+    deck = await program.account.deckAccount.fetch(deckAccount4, "confirmed");
+    expect(deck.lockedGame.toBase58()).to.equal(gameAccount4.toBase58());
+  });
+
   async function initAddTogetherCompDef(
     program: Program<KnostraArcium>,
     owner: anchor.web3.Keypair,
@@ -736,6 +1509,174 @@ describe("KnostraArcium", () => {
   }
 });
 
+// Mirrors `ComputationKind` in the program
+enum ComputationKind {
+  InitGame,
+  JoinGame,
+  PlayTurn,
+}
+
+// Mirrors `GameAccount::computation_offset`: the first 8 bytes of
+// sha256("computation" || game || kind || turn || side || failures), little endian.
+function gameComputationOffset(
+  game: PublicKey,
+  kind: ComputationKind,
+  turn: number,
+  side: number,
+  failedComputations: number
+): anchor.BN {
+  const failures = Buffer.alloc(4);
+  failures.writeUInt32LE(failedComputations);
+  const hash = createHash("sha256")
+    .update(Buffer.from("computation"))
+    .update(game.toBuffer())
+    .update(Buffer.from([kind, turn, side]))
+    .update(failures)
+    .digest();
+  return new anchor.BN(hash.subarray(0, 8), "le");
+}
+
+const MPL_CORE_PROGRAM_ID = new PublicKey(
+  "CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d"
+);
+
+function freezeAuthority(programId: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("freeze_authority")],
+    programId
+  )[0];
+}
+
+function borshString(value: string): Buffer {
+  const bytes = Buffer.from(value);
+  const len = Buffer.alloc(4);
+  len.writeUInt32LE(bytes.length);
+  return Buffer.concat([len, bytes]);
+}
+
+// mpl-core `CreateV1` of an asset owned by `owner` with card stats in its
+// Attributes plugin, managed by its update authority
+function createCoreCardIx(
+  asset: PublicKey,
+  owner: PublicKey
+): TransactionInstruction {
+  const attributes = [
+    ["power", "7"],
+    ["element", "water"],
+    ["rarity", "rare"],
+  ];
+  const count = Buffer.alloc(4);
+  count.writeUInt32LE(attributes.length);
+  const data = Buffer.concat([
+    Buffer.from([0, 0]), // CreateV1, DataState::AccountState
+    borshString("Card"),
+    borshString(""),
+    Buffer.from([1, 1, 0, 0, 0]), // Some(vec![...]) of one plugin
+    Buffer.from([6]), // Plugin::Attributes
+    count,
+    ...attributes.flatMap(([key, value]) => [
+      borshString(key),
+      borshString(value),
+    ]),
+    Buffer.from([0]), // default authority
+  ]);
+  // Optional accounts left out are passed as the mpl-core program
+  const none = {
+    pubkey: MPL_CORE_PROGRAM_ID,
+    isSigner: false,
+    isWritable: false,
+  };
+  return new TransactionInstruction({
+    programId: MPL_CORE_PROGRAM_ID,
+    keys: [
+      { pubkey: asset, isSigner: true, isWritable: true },
+      none, // collection
+      none, // authority
+      { pubkey: owner, isSigner: true, isWritable: true }, // payer
+      none, // owner, the payer
+      none, // update authority, the payer
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      none, // log wrapper
+    ],
+    data,
+  });
+}
+
+// mpl-core `TransferV1` of `asset` from `owner`
+function transferCoreCardIx(
+  asset: PublicKey,
+  owner: PublicKey,
+  newOwner: PublicKey
+): TransactionInstruction {
+  const none = {
+    pubkey: MPL_CORE_PROGRAM_ID,
+    isSigner: false,
+    isWritable: false,
+  };
+  return new TransactionInstruction({
+    programId: MPL_CORE_PROGRAM_ID,
+    keys: [
+      { pubkey: asset, isSigner: false, isWritable: true },
+      none, // collection
+      { pubkey: owner, isSigner: true, isWritable: true }, // payer
+      none, // authority, the payer
+      { pubkey: newOwner, isSigner: false, isWritable: false },
+      none, // system program
+      none, // log wrapper
+    ],
+    data: Buffer.from([14, 0]), // TransferV1 without a compression proof
+  });
+}
+
+// The FreezeDelegate plugin of an mpl-core asset account, read off its plugin
+// registry: the asset, a plugin header pointing at the registry, the plugins.
+function coreFreezeDelegate(
+  data: Buffer
+): { frozen: boolean; authority: PublicKey | null } | null {
+  const registryOffset = Number(data.readBigUInt64LE(assetLength(data) + 1));
+  let cursor = registryOffset + 1;
+  const records = data.readUInt32LE(cursor);
+  cursor += 4;
+  for (let i = 0; i < records; i++) {
+    const pluginType = data[cursor];
+    const authorityKind = data[cursor + 1];
+    cursor += 2;
+    let authority: PublicKey | null = null;
+    if (authorityKind === 3) {
+      authority = new PublicKey(data.subarray(cursor, cursor + 32));
+      cursor += 32;
+    }
+    const offset = Number(data.readBigUInt64LE(cursor));
+    cursor += 8;
+    // PluginType::FreezeDelegate, stored as Plugin::FreezeDelegate { frozen }
+    if (pluginType === 1) {
+      return { frozen: data[offset + 1] === 1, authority };
+    }
+  }
+  return null;
+}
+
+// Length of the `BaseAssetV1` an mpl-core asset account starts with
+function assetLength(data: Buffer): number {
+  let cursor = 1 + 32; // key, owner
+  cursor += data[cursor] === 0 ? 1 : 33; // update authority
+  for (let i = 0; i < 2; i++) {
+    cursor += 4 + data.readUInt32LE(cursor); // name, uri
+  }
+  cursor += data[cursor] === 0 ? 1 : 9; // seq
+  return cursor;
+}
+
+// Mirrors `GameAccount::add_together_offset`; `nonce` is the u128 in little endian
+function addTogetherOffset(payer: PublicKey, nonce: Buffer): anchor.BN {
+  const hash = createHash("sha256")
+    .update(Buffer.from("add_together"))
+    .update(payer.toBuffer())
+    .update(nonce)
+    .digest();
+  return new anchor.BN(hash.subarray(0, 8), "le");
+}
+
 async function getMXEPublicKeyWithRetry(
   provider: anchor.AnchorProvider,
   programId: PublicKey,
@@ -824,6 +1765,125 @@ async function initInitGameCompDef(
   }
   return sig;
 }
+
+async function initJoinGameCompDef(
+  program: Program<KnostraArcium>,
+  owner: anchor.web3.Keypair,
+  uploadRawCircuit: boolean,
+  offchainSource: boolean
+): Promise<string> {
+  const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+    "ComputationDefinitionAccount"
+  );
+  const offset = getCompDefAccOffset("join_game");
+
+  const compDefPDA = PublicKey.findProgramAddressSync(
+    [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+    getArciumProgAddress()
+  )[0];
+
+  console.log(`Comp def PDA for join_game:`, compDefPDA.toBase58());
+
+  const sig = await program.methods
+    .initJoinGameCompDef()
+    .accounts({
+      compDefAccount: compDefPDA,
+      payer: owner.publicKey,
+      mxeAccount: getMXEAccAddress(program.programId),
+    })
+    .signers([owner])
+    .rpc({
+      commitment: "confirmed",
+    });
+
+  console.log(`Init join_game computation definition transaction`, sig);
+
+  if (uploadRawCircuit) {
+    const rawCircuit = fs.readFileSync(`build/join_game.arcis`);
+    await uploadCircuit(
+      program.provider as anchor.AnchorProvider,
+      "join_game",
+      program.programId,
+      rawCircuit,
+      true
+    );
+  } else if (!offchainSource) {
+    const finalizeTx = await buildFinalizeCompDefTx(
+      program.provider as anchor.AnchorProvider,
+      Buffer.from(offset).readUInt32LE(),
+      program.programId
+    );
+
+    const latestBlockhash =
+      await program.provider.connection.getLatestBlockhash();
+    finalizeTx.recentBlockhash = latestBlockhash.blockhash;
+    finalizeTx.lastValidBlockHeight = latestBlockhash.lastValidBlockHeight;
+
+    finalizeTx.sign(owner);
+    await program.provider.sendAndConfirm(finalizeTx);
+  }
+  return sig;
+}
+
+async function initPlayTurnCompDef(
+  program: Program<KnostraArcium>,
+  owner: anchor.web3.Keypair,
+  uploadRawCircuit: boolean,
+  offchainSource: boolean
+): Promise<string> {
+  const baseSeedCompDefAcc = getArciumAccountBaseSeed(
+    "ComputationDefinitionAccount"
+  );
+  const offset = getCompDefAccOffset("play_turn");
+
+  const compDefPDA = PublicKey.findProgramAddressSync(
+    [baseSeedCompDefAcc, program.programId.toBuffer(), offset],
+    getArciumProgAddress()
+  )[0];
+
+  console.log(`Comp def PDA for play_turn:`, compDefPDA.toBase58());
+
+  const sig = await program.methods
+    .initPlayTurnCompDef()
+    .accounts({
+      compDefAccount: compDefPDA,
+      payer: owner.publicKey,
+      mxeAccount: getMXEAccAddress(program.programId),
+    })
+    .signers([owner])
+    .rpc({
+      commitment: "confirmed",
+    });
+
+  console.log(`Init play_turn computation definition transaction`, sig);
+
+  if (uploadRawCircuit) {
+    const rawCircuit = fs.readFileSync(`build/play_turn.arcis`);
+    await uploadCircuit(
+      program.provider as anchor.AnchorProvider,
+      "play_turn",
+      program.programId,
+      rawCircuit,
+      true
+    );
+  } else if (!offchainSource) {
+    const finalizeTx = await buildFinalizeCompDefTx(
+      program.provider as anchor.AnchorProvider,
+      Buffer.from(offset).readUInt32LE(),
+      program.programId
+    );
+
+    const latestBlockhash =
+      await program.provider.connection.getLatestBlockhash();
+    finalizeTx.recentBlockhash = latestBlockhash.blockhash;
+    finalizeTx.lastValidBlockHeight = latestBlockhash.lastValidBlockHeight;
+
+    finalizeTx.sign(owner);
+    await program.provider.sendAndConfirm(finalizeTx);
+  }
+  return sig;
+}
+
 function readKpJson(path: string): anchor.web3.Keypair {
   const file = fs.readFileSync(path);
   return anchor.web3.Keypair.fromSecretKey(